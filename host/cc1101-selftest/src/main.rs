@@ -0,0 +1,38 @@
+use std::{io::Write, process::ExitCode};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use rf_tool::transceiver::cc1101::Cc1101;
+
+#[derive(Debug, clap::Parser)]
+struct Cli {
+    #[arg(long)]
+    port: String,
+}
+
+fn run(cli: Cli) -> Result<bool> {
+    let mut port = serialport::new(cli.port, 115200)
+        .open_native()
+        .context("failed to open serial port")?;
+    port.write_all(&[0x01])
+        .context("failed to select endpoint")?;
+
+    let mut cc1101 = Cc1101::new(&mut port);
+    let report = cc1101.self_test().context("failed to run self test")?;
+    cc1101.exit().context("failed to exit endpoint")?;
+
+    println!("{report}");
+    Ok(report.passed())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
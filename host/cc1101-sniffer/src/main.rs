@@ -0,0 +1,118 @@
+use std::{fs::File, io::Write, process::ExitCode, time::Duration, time::SystemTime};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use rf_tool::transceiver::cc1101::{
+    freq::XOSC_FREQ,
+    rx::{RxItem, RxMode},
+    Cc1101, ConfigRegAddr,
+};
+
+mod pcapng;
+use pcapng::{FrameHeader, PcapngWriter};
+
+#[derive(Debug, clap::Parser)]
+struct Cli {
+    #[arg(long)]
+    port: String,
+    /// pcapng file to write
+    #[arg(long)]
+    output: String,
+    /// Base frequency in Hz, keep current setting if not specified
+    #[arg(long)]
+    frequency: Option<u64>,
+    /// Crystal frequency in Hz
+    #[arg(long, default_value_t = XOSC_FREQ)]
+    xosc: u32,
+    /// RX FIFO poll interval in ms
+    #[arg(long, default_value_t = 5)]
+    interval: u64,
+    /// Stop after this many frames
+    #[arg(long)]
+    count: Option<usize>,
+}
+
+fn sniff<P: std::io::Read + Write>(
+    cli: &Cli,
+    cc1101: &mut Cc1101<P>,
+    writer: &mut PcapngWriter<File>,
+) -> Result<()> {
+    if let Some(frequency) = cli.frequency {
+        cc1101
+            .set_base_frequency(cli.xosc, frequency)
+            .context("failed to set frequency")?;
+    }
+    // variable packet length with RSSI and LQI appended
+    cc1101
+        .modify_config_reg(ConfigRegAddr::PKTCTRL0, 0x03, 0x01)
+        .context("failed to set packet length mode")?;
+    cc1101
+        .modify_config_reg(ConfigRegAddr::PKTCTRL1, 0x04, 0x04)
+        .context("failed to enable status append")?;
+    let frequency = cc1101
+        .read_carrier_frequency(cli.xosc)
+        .context("failed to read frequency")?;
+    println!("sniffing at {frequency} Hz");
+
+    let mut count = 0;
+    let mut receiver = cc1101.receiver(
+        RxMode::Packet {
+            append_status: true,
+        },
+        Duration::from_millis(cli.interval),
+    );
+    while cli.count.is_none_or(|c| count < c) {
+        let Some(item) = receiver.next() else {
+            break;
+        };
+        let RxItem::Packet(packet) = item.context("failed to receive")? else {
+            continue;
+        };
+        let header = FrameHeader {
+            timestamp: SystemTime::now(),
+            frequency,
+            status: packet.status,
+        };
+        println!(
+            "[{count}] {} bytes, rssi {:?} dBm, lqi {:?}, crc_ok {:?}, {} dropped",
+            packet.data.len(),
+            packet.rssi_dbm(),
+            packet.lqi(),
+            packet.crc_ok(),
+            receiver.drops()
+        );
+        writer
+            .write_frame(&header, &packet.data)
+            .context("failed to write frame")?;
+        count += 1;
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut writer =
+        PcapngWriter::new(File::create_new(&cli.output).context("failed to create output file")?)
+            .context("failed to write pcapng header")?;
+
+    let mut port = serialport::new(&cli.port, 115200)
+        .open_native()
+        .context("failed to open serial port")?;
+    port.write_all(&[0x01])
+        .context("failed to select endpoint")?;
+
+    let mut cc1101 = Cc1101::new(&mut port);
+    let ret = sniff(&cli, &mut cc1101, &mut writer);
+    cc1101.exit().context("failed to exit endpoint")?;
+    ret
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
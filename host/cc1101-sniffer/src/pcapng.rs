@@ -0,0 +1,146 @@
+//! Minimal pcapng writer, see <https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html>
+
+use std::{
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// `LINKTYPE_USER0`, frames start with [`FrameHeader`]
+pub const LINKTYPE: u16 = 147;
+
+/// Link layer header prepended to every frame, all fields little endian
+#[derive(Debug, Clone, Copy)]
+pub struct FrameHeader {
+    pub timestamp: SystemTime,
+    /// Carrier frequency in Hz
+    pub frequency: u64,
+    /// Raw `RSSI` and `LQI | CRC_OK << 7`
+    pub status: Option<[u8; 2]>,
+}
+impl FrameHeader {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 20;
+
+    /// ```text
+    /// 0  version
+    /// 1  flags: bit 0 status valid, bit 1 CRC_OK
+    /// 2  RSSI (raw)
+    /// 3  LQI
+    /// 4  frequency in Hz (u64)
+    /// 12 timestamp in ns since unix epoch (u64)
+    /// ```
+    pub fn to_bytes(self) -> [u8; Self::LEN] {
+        let mut ret = [0; Self::LEN];
+        ret[0] = Self::VERSION;
+        if let Some([rssi, lqi]) = self.status {
+            ret[1] = 0x01 | if lqi & 0x80 != 0 { 0x02 } else { 0 };
+            ret[2] = rssi;
+            ret[3] = lqi & 0x7f;
+        }
+        ret[4..12].copy_from_slice(&self.frequency.to_le_bytes());
+        ret[12..20].copy_from_slice(&timestamp_ns(self.timestamp).to_le_bytes());
+        ret
+    }
+}
+
+fn timestamp_ns(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64
+}
+
+fn write_block(writer: &mut impl Write, block_type: u32, body: &[u8]) -> io::Result<()> {
+    let padding = (4 - body.len() % 4) % 4;
+    let total_len = (12 + body.len() + padding) as u32;
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&total_len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&[0; 3][..padding])?;
+    writer.write_all(&total_len.to_le_bytes())
+}
+
+pub struct PcapngWriter<W: Write>(W);
+impl<W: Write> PcapngWriter<W> {
+    /// Write section header and a single interface with nanosecond timestamps
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut shb = Vec::new();
+        shb.extend(0x1A2B3C4Du32.to_le_bytes());
+        shb.extend(1u16.to_le_bytes());
+        shb.extend(0u16.to_le_bytes());
+        shb.extend((-1i64).to_le_bytes());
+        write_block(&mut writer, 0x0A0D0D0A, &shb)?;
+
+        let mut idb = Vec::new();
+        idb.extend(LINKTYPE.to_le_bytes());
+        idb.extend(0u16.to_le_bytes());
+        idb.extend(0u32.to_le_bytes());
+        // if_tsresol = 10^-9
+        idb.extend([9, 0, 1, 0, 9, 0, 0, 0]);
+        // opt_endofopt
+        idb.extend([0; 4]);
+        write_block(&mut writer, 0x00000001, &idb)?;
+
+        Ok(Self(writer))
+    }
+
+    /// Write frame as enhanced packet block and flush
+    pub fn write_frame(&mut self, header: &FrameHeader, data: &[u8]) -> io::Result<()> {
+        let ts = timestamp_ns(header.timestamp);
+        let len = (FrameHeader::LEN + data.len()) as u32;
+        let mut epb = Vec::with_capacity(20 + len as usize);
+        epb.extend(0u32.to_le_bytes());
+        epb.extend(((ts >> 32) as u32).to_le_bytes());
+        epb.extend((ts as u32).to_le_bytes());
+        epb.extend(len.to_le_bytes());
+        epb.extend(len.to_le_bytes());
+        epb.extend(header.to_bytes());
+        epb.extend(data);
+        write_block(&mut self.0, 0x00000006, &epb)?;
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn u32_at(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn one_packet_section() {
+        let mut buf = Vec::new();
+        let mut writer = PcapngWriter::new(&mut buf).unwrap();
+        let header = FrameHeader {
+            timestamp: UNIX_EPOCH + Duration::from_nanos(0x1_0000_0002),
+            frequency: 433_920_000,
+            status: Some([0x2c, 0x81]),
+        };
+        writer.write_frame(&header, &[0xa0, 0xa1, 0xa2]).unwrap();
+        assert_eq!(buf.len(), 28 + 32 + 56);
+
+        let (shb, rest) = buf.split_at(28);
+        assert_eq!(u32_at(shb, 0), 0x0A0D0D0A);
+        assert_eq!((u32_at(shb, 4), u32_at(shb, 24)), (28, 28));
+        assert_eq!(shb[8..12], [0x4d, 0x3c, 0x2b, 0x1a]);
+
+        let (idb, epb) = rest.split_at(32);
+        assert_eq!(u32_at(idb, 0), 1);
+        assert_eq!((u32_at(idb, 4), u32_at(idb, 28)), (32, 32));
+        assert_eq!(idb[8..10], LINKTYPE.to_le_bytes());
+        assert_eq!(idb[16..24], [9, 0, 1, 0, 9, 0, 0, 0]);
+
+        assert_eq!(u32_at(epb, 0), 6);
+        assert_eq!((u32_at(epb, 4), u32_at(epb, 52)), (56, 56));
+        // timestamp high and low, captured and original length
+        assert_eq!(
+            [12, 16, 20, 24].map(|offset| u32_at(epb, offset)),
+            [1, 2, 23, 23]
+        );
+        assert_eq!(epb[28..48], header.to_bytes());
+        assert_eq!(epb[28..32], [1, 0x03, 0x2c, 0x01]);
+        // 43 byte body padded to 44
+        assert_eq!(epb[48..52], [0xa0, 0xa1, 0xa2, 0]);
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rustyline::error::ReadlineError;
+
+use rf_tool::{
+    trace::Tracer,
+    transceiver::cc1101::{
+        freq::XOSC_FREQ,
+        region::{Region, Regulation},
+        Cc1101, CommandStrobe, ConfigRegAddr, ReadWrite, StatusRegAddr,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+enum Reg {
+    Config(ConfigRegAddr),
+    Status(StatusRegAddr),
+}
+impl FromStr for Reg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ConfigRegAddr::from_str(s)
+            .map(Reg::Config)
+            .or_else(|_| StatusRegAddr::from_str(s).map(Reg::Status))
+            .map_err(|_| format!("unknown register {s}"))
+    }
+}
+
+fn parse_u8(s: &str) -> Result<u8, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn parse_region(s: &str) -> Result<&'static Region, String> {
+    Region::by_name(s).ok_or_else(|| {
+        let names: Vec<_> = Region::ALL.iter().map(|r| r.name).collect();
+        format!("unknown region {s}, expected one of {}", names.join(", "))
+    })
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum FifoOp {
+    /// Burst read bytes from RX FIFO
+    Read { count: usize },
+    /// Burst write bytes to TX FIFO
+    Write {
+        #[arg(value_parser = parse_u8, required = true)]
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Read config or status registers by name
+    Peek {
+        #[arg(required = true)]
+        regs: Vec<Reg>,
+    },
+    /// Write config register
+    Poke {
+        reg: ConfigRegAddr,
+        #[arg(value_parser = parse_u8)]
+        value: u8,
+    },
+    /// Send command strobe
+    Strobe { strobe: CommandStrobe },
+    /// Print all config registers, or write them to a file
+    Dump {
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Write config registers from a file written by `dump`, except `FSTEST`, `PTEST` and
+    /// `AGCTEST`
+    Load { input: PathBuf },
+    /// Show config registers differing from a file written by `dump`, or from reset values
+    Diff { snapshot: Option<PathBuf> },
+    /// Read PATABLE, or write it if 8 values are given
+    Patable {
+        #[arg(value_parser = parse_u8)]
+        values: Vec<u8>,
+    },
+    Fifo {
+        #[command(subcommand)]
+        op: FifoOp,
+    },
+    /// Print chip status
+    Status,
+    /// Run self test, which resets the chip
+    Selftest,
+    /// Read commands interactively
+    Repl {
+        /// History file
+        #[arg(long)]
+        history: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, clap::Parser)]
+struct Cli {
+    #[arg(long)]
+    port: String,
+    /// Record serial traffic to this file
+    #[arg(long)]
+    trace: Option<PathBuf>,
+    /// Refuse frequencies and PATABLE values not allowed in this region
+    #[arg(long, value_parser = parse_region)]
+    region: Option<&'static Region>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Parser)]
+#[command(multicall = true)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: Command,
+}
+
+fn read_config<P: Read + Write>(cc1101: &mut Cc1101<P>) -> Result<[u8; ConfigRegAddr::ALL.len()]> {
+    Ok(cc1101
+        .read_raw_config_burst(ConfigRegAddr::IOCFG2)
+        .context("failed to read config registers")?
+        .1)
+}
+
+fn dump<P: Read + Write>(cc1101: &mut Cc1101<P>) -> Result<String> {
+    Ok(ConfigRegAddr::ALL
+        .into_iter()
+        .zip(read_config(cc1101)?)
+        .map(|(addr, v)| format!("{addr:?} = {v:#04x} # {}\n", addr.describe(v)))
+        .collect())
+}
+
+fn parse_dump(input: &str) -> Result<Vec<(ConfigRegAddr, u8)>> {
+    let mut ret = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((reg, value)) = line.split_once('=') else {
+            bail!("line {}: expected `REG = VALUE`", idx + 1);
+        };
+        let reg = ConfigRegAddr::from_str(reg.trim())
+            .with_context(|| format!("line {}: unknown register", idx + 1))?;
+        let value = parse_u8(value.trim())
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("line {}: invalid value", idx + 1))?;
+        ret.push((reg, value));
+    }
+    Ok(ret)
+}
+
+/// `CHANNR..=MDMCFG0`, which set the carrier frequency
+const FREQ_BLOCK: usize = ConfigRegAddr::MDMCFG0 as usize - ConfigRegAddr::CHANNR as usize + 1;
+
+/// Registers which should never be written are skipped. Frequency registers are written last
+/// in one burst, so a region only checks the final frequency.
+fn load<P: Read + Write>(cc1101: &mut Cc1101<P>, input: &str) -> Result<()> {
+    let mut freq_block = None;
+    for (reg, value) in parse_dump(input)? {
+        if reg.is_test_only() {
+            println!("{reg:?}: skipped, test only register");
+            continue;
+        }
+        if let Some(idx) = (reg as usize)
+            .checked_sub(ConfigRegAddr::CHANNR as usize)
+            .filter(|idx| *idx < FREQ_BLOCK)
+        {
+            let block = match &mut freq_block {
+                Some(block) => block,
+                None => freq_block.insert(
+                    cc1101
+                        .read_raw_config_burst::<FREQ_BLOCK>(ConfigRegAddr::CHANNR)
+                        .context("failed to read frequency registers")?
+                        .1,
+                ),
+            };
+            block[idx] = value;
+            continue;
+        }
+        cc1101
+            .write_raw_config_reg(true, reg, value)
+            .with_context(|| format!("failed to write {reg:?}"))?;
+    }
+    if let Some(block) = freq_block {
+        cc1101
+            .write_raw_config_burst(ConfigRegAddr::CHANNR, &block)
+            .context("failed to write CHANNR..MDMCFG0")?;
+    }
+    Ok(())
+}
+
+fn diff<P: Read + Write>(cc1101: &mut Cc1101<P>, snapshot: Option<&str>) -> Result<()> {
+    let expected = match snapshot {
+        Some(snapshot) => parse_dump(snapshot)?,
+        None => ConfigRegAddr::ALL
+            .into_iter()
+            .map(|addr| (addr, addr.reset_value()))
+            .collect(),
+    };
+    let values = read_config(cc1101)?;
+    for (addr, expected) in expected {
+        let v = values[addr as usize];
+        if v != expected {
+            println!(
+                "{addr:?}: {expected:#04x} ({}) -> {v:#04x} ({})",
+                addr.describe(expected),
+                addr.describe(v)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn execute<P: Read + Write>(cc1101: &mut Cc1101<P>, command: Command) -> Result<()> {
+    match command {
+        Command::Peek { regs } => {
+            for reg in regs {
+                let (status, v) = match reg {
+                    Reg::Config(addr) => cc1101.read_raw_config_reg(true, addr),
+                    Reg::Status(addr) => cc1101.read_raw_status_reg(true, addr),
+                }
+                .context("failed to read register")?;
+                println!("{reg:?} = {v:#04x} (status {status:?})");
+            }
+        }
+        Command::Poke { reg, value } => {
+            let (status, _) = cc1101
+                .write_raw_config_reg(true, reg, value)
+                .context("failed to write register")?;
+            println!("status {status:?}");
+        }
+        Command::Strobe { strobe } => {
+            let status = cc1101
+                .command_strobe(true, ReadWrite::Write, strobe)
+                .context("failed to send strobe")?;
+            println!("status {status:?}");
+        }
+        Command::Dump { output } => {
+            let dump = dump(cc1101)?;
+            match output {
+                Some(path) => std::fs::write(path, dump).context("failed to write dump")?,
+                None => print!("{dump}"),
+            }
+        }
+        Command::Load { input } => {
+            load(
+                cc1101,
+                &std::fs::read_to_string(input).context("failed to read input")?,
+            )?;
+        }
+        Command::Diff { snapshot } => {
+            let snapshot = snapshot
+                .map(std::fs::read_to_string)
+                .transpose()
+                .context("failed to read snapshot")?;
+            diff(cc1101, snapshot.as_deref())?;
+        }
+        Command::Patable { values } => {
+            if values.is_empty() {
+                let (_, table) = cc1101.read_pa_table().context("failed to read PATABLE")?;
+                println!("{table:02x?}");
+            } else {
+                let Ok(table) = <[u8; 8]>::try_from(values) else {
+                    bail!("PATABLE has 8 entries");
+                };
+                cc1101
+                    .write_pa_table(&table)
+                    .context("failed to write PATABLE")?;
+            }
+        }
+        Command::Fifo {
+            op: FifoOp::Read { count },
+        } => {
+            let mut buf = vec![0; count];
+            let status = cc1101
+                .read_fifo_into(&mut buf)
+                .context("failed to read fifo")?;
+            println!("{buf:02x?} (status {status:?})");
+        }
+        Command::Fifo {
+            op: FifoOp::Write { data },
+        } => {
+            let (_, status) = cc1101
+                .write_fifo_from(&data)
+                .context("failed to write fifo")?;
+            println!("status {:?}", status.last().unwrap());
+        }
+        Command::Status => {
+            let status = cc1101
+                .command_strobe(true, ReadWrite::Read, CommandStrobe::SNOP)
+                .context("failed to read status")?;
+            println!(
+                "status {status:?}: {:?}, {} bytes in RX FIFO, chip ready {}",
+                status.state(),
+                status.fifo_bytes_available(),
+                status.chip_ready()
+            );
+            println!(
+                "MARCSTATE {:?}",
+                cc1101
+                    .read_marc_state()
+                    .context("failed to read MARCSTATE")?
+            );
+            println!(
+                "{:?}",
+                cc1101
+                    .read_packet_status()
+                    .context("failed to read PKTSTATUS")?
+            );
+            for addr in [StatusRegAddr::RXBYTES, StatusRegAddr::TXBYTES] {
+                let v = cc1101
+                    .read_raw_status_reg(true, addr)
+                    .context("failed to read register")?
+                    .1;
+                println!("{addr:?} = {v:#04x}");
+            }
+        }
+        Command::Selftest => {
+            let report = cc1101.self_test().context("failed to run self test")?;
+            println!("{report}");
+        }
+        Command::Repl { .. } => bail!("already in repl"),
+    }
+    Ok(())
+}
+
+fn repl<P: Read + Write>(cc1101: &mut Cc1101<P>, history: Option<PathBuf>) -> Result<()> {
+    let mut editor = rustyline::DefaultEditor::new().context("failed to create editor")?;
+    if let Some(history) = &history {
+        // history file may not exist yet
+        let _ = editor.load_history(history);
+    }
+    loop {
+        let line = match editor.readline("cc1101> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(e).context("failed to read line"),
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        editor
+            .add_history_entry(line.as_str())
+            .context("failed to add history")?;
+        if words == ["exit"] || words == ["quit"] {
+            break;
+        }
+        match ReplLine::try_parse_from(words) {
+            Ok(ReplLine { command }) => {
+                if let Err(e) = execute(cc1101, command) {
+                    eprintln!("{e:?}");
+                }
+            }
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+    if let Some(history) = &history {
+        editor
+            .save_history(history)
+            .context("failed to save history")?;
+    }
+    Ok(())
+}
+
+fn run_on<P: Read + Write>(
+    port: &mut P,
+    region: Option<&'static Region>,
+    command: Command,
+) -> Result<()> {
+    port.write_all(&[0x01])
+        .context("failed to select endpoint")?;
+
+    let mut cc1101 = Cc1101::new(port);
+    cc1101.set_regulation(region.map(|r| Regulation::new(r, XOSC_FREQ)));
+    let ret = match command {
+        Command::Repl { history } => repl(&mut cc1101, history),
+        command => execute(&mut cc1101, command),
+    };
+    cc1101.exit().context("failed to exit endpoint")?;
+    ret
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut port = serialport::new(cli.port, 115200)
+        .open_native()
+        .context("failed to open serial port")?;
+
+    match cli.trace {
+        Some(trace) => run_on(
+            &mut Tracer::new(
+                port,
+                File::create_new(trace).context("failed to create trace file")?,
+            ),
+            cli.region,
+            cli.command,
+        ),
+        None => run_on(&mut port, cli.region, cli.command),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
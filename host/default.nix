@@ -35,6 +35,11 @@ rustPlatform.buildRustPackage {
     "
   '';
 
+  # the no_std encoding core is not covered by the workspace test run
+  postCheck = ''
+    cargo test --offline -p rf-tool --no-default-features
+  '';
+
   buildInputs = [
     udev
     zlib.dev
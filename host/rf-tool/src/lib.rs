@@ -1,6 +1,14 @@
+// tests link std for `Vec` and friends even without the std feature
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
 pub mod remote_control {
     pub mod atsmart;
+    pub mod ev1527;
+    pub mod princeton;
+    pub mod pwm;
 }
+#[cfg(feature = "std")]
+pub mod trace;
 pub mod transceiver {
     pub mod cc1101;
 }
@@ -1,9 +1,21 @@
 pub mod ykq368 {
-    use std::{fmt::Debug, io};
+    use core::{fmt::Debug, time::Duration};
+    #[cfg(feature = "std")]
+    use std::io;
 
     use serde::{Deserialize, Serialize};
 
-    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub use crate::remote_control::pwm::PwmTiming;
+
+    #[cfg(all(feature = "std", feature = "ykq368-frame"))]
+    pub mod pairing;
+    #[cfg(feature = "std")]
+    pub mod remotes;
+    #[cfg(feature = "std")]
+    pub mod schedule;
+    pub mod waveform;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "snake_case")]
     pub enum SendParts {
         Preamble,
@@ -11,10 +23,10 @@ pub mod ykq368 {
         All,
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct Preamble(pub u16);
     impl Debug for Preamble {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
             write!(f, "{:04x}", self.0)
         }
     }
@@ -23,14 +35,27 @@ pub mod ykq368 {
         where
             S: serde::Serializer,
         {
-            serializer.serialize_bytes(&self.0.to_be_bytes())
+            if serializer.is_human_readable() {
+                serializer.collect_str(&format_args!("0x{:04x}", self.0))
+            } else {
+                serializer.serialize_bytes(&self.0.to_be_bytes())
+            }
+        }
+    }
+    impl<'de> Deserialize<'de> for Preamble {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let value = deserializer.deserialize_any(BeVisitor::<2>)?;
+            Self::new(value as u16).map_err(serde::de::Error::custom)
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct Data(pub u64);
     impl Debug for Data {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
             write!(f, "{:09x}", self.0)
         }
     }
@@ -39,11 +64,62 @@ pub mod ykq368 {
         where
             S: serde::Serializer,
         {
-            serializer.serialize_bytes(&self.0.to_be_bytes()[3..])
+            if serializer.is_human_readable() {
+                serializer.collect_str(&format_args!("0x{:09x}", self.0))
+            } else {
+                serializer.serialize_bytes(&self.0.to_be_bytes()[3..])
+            }
+        }
+    }
+    impl<'de> Deserialize<'de> for Data {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let value = deserializer.deserialize_any(BeVisitor::<5>)?;
+            Self::new(value).map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// Accepts `N` big-endian bytes or a hex string of at most `N` bytes, as written by
+    /// `Serialize` to binary and human-readable formats
+    struct BeVisitor<const N: usize>;
+    impl<'de, const N: usize> serde::de::Visitor<'de> for BeVisitor<N> {
+        type Value = u64;
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            write!(formatter, "{N} byte string or hex string")
+        }
+        fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            if v.len() != N {
+                return Err(E::invalid_length(v.len(), &self));
+            }
+            Ok(v.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+        }
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(
+            self,
+            mut seq: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut buf = [0; N];
+            for (idx, b) in buf.iter_mut().enumerate() {
+                *b = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(idx, &self))?;
+            }
+            if seq.next_element::<u8>()?.is_some() {
+                return Err(serde::de::Error::invalid_length(N + 1, &self));
+            }
+            self.visit_bytes(&buf)
+        }
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            let hex = v.strip_prefix("0x").unwrap_or(v);
+            match u64::from_str_radix(hex, 16) {
+                Ok(value) if !hex.starts_with('+') && value >> (N * 8) == 0 => Ok(value),
+                _ => Err(E::invalid_value(serde::de::Unexpected::Str(v), &self)),
+            }
         }
     }
 
-    #[derive(Debug, Clone, Copy, Serialize)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
     pub struct SendInstr {
         pub send: SendParts,
         pub skip: u8,
@@ -55,7 +131,7 @@ pub mod ykq368 {
     #[derive(Clone, Copy, PartialEq, Eq)]
     pub struct Command([u8; 8]);
     impl Debug for Command {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
             write!(f, "Command(")?;
             for v in self.0 {
                 write!(f, "{v:02x}")?;
@@ -63,17 +139,150 @@ pub mod ykq368 {
             f.write_str(")")
         }
     }
+    impl From<[u8; 8]> for Command {
+        fn from(value: [u8; 8]) -> Self {
+            Self(value)
+        }
+    }
+    impl From<Command> for [u8; 8] {
+        fn from(value: Command) -> Self {
+            value.0
+        }
+    }
+
+    /// Field value that doesn't fit in [`Command`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum RangeError {
+        Preamble(u16),
+        Data(u64),
+        Skip(u8),
+        /// The FPGA `InstrProc` ignores instructions without repeats
+        ZeroRepeat,
+    }
+    impl core::fmt::Display for RangeError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Self::Preamble(v) => write!(f, "preamble {v:#x} exceeds {:#x}", Preamble::MAX),
+                Self::Data(v) => write!(f, "data {v:#x} exceeds {:#x}", Data::MAX),
+                Self::Skip(v) => write!(f, "skip {v} exceeds {}", SendInstr::MAX_SKIP),
+                Self::ZeroRepeat => f.write_str("repeat is zero"),
+            }
+        }
+    }
+    impl core::error::Error for RangeError {}
+
+    /// Decimal, or hex with a `0x` prefix, for preambles given on the command line
+    pub fn parse_u16(s: &str) -> Result<u16, core::num::ParseIntError> {
+        match s.strip_prefix("0x") {
+            Some(hex) => u16::from_str_radix(hex, 16),
+            None => s.parse(),
+        }
+    }
+    /// As [`parse_u16`], for data words
+    pub fn parse_u64(s: &str) -> Result<u64, core::num::ParseIntError> {
+        match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => s.parse(),
+        }
+    }
+
+    impl Preamble {
+        /// 13 bit field
+        pub const MAX: u16 = 0x1fff;
+        pub const fn new(value: u16) -> Result<Self, RangeError> {
+            if value > Self::MAX {
+                Err(RangeError::Preamble(value))
+            } else {
+                Ok(Self(value))
+            }
+        }
+    }
+    impl Data {
+        /// 35 bit field
+        pub const MAX: u64 = 0x7_ffff_ffff;
+        pub const fn new(value: u64) -> Result<Self, RangeError> {
+            if value > Self::MAX {
+                Err(RangeError::Data(value))
+            } else {
+                Ok(Self(value))
+            }
+        }
+    }
+
+    /// Builder checking every field, created by [`SendInstr::builder`]
+    #[derive(Debug, Clone, Copy)]
+    pub struct SendInstrBuilder {
+        send: SendParts,
+        skip: u8,
+        preamble: u16,
+        data: u64,
+        repeat: u8,
+    }
+    impl SendInstrBuilder {
+        pub const fn skip(self, skip: u8) -> Self {
+            Self { skip, ..self }
+        }
+        pub const fn preamble(self, preamble: u16) -> Self {
+            Self { preamble, ..self }
+        }
+        pub const fn data(self, data: u64) -> Self {
+            Self { data, ..self }
+        }
+        pub const fn repeat(self, repeat: u8) -> Self {
+            Self { repeat, ..self }
+        }
+        pub fn build(self) -> Result<SendInstr, RangeError> {
+            let ret = SendInstr {
+                send: self.send,
+                skip: self.skip,
+                preamble: Preamble::new(self.preamble)?,
+                data: Data::new(self.data)?,
+                repeat: self.repeat,
+            };
+            ret.validate()?;
+            Ok(ret)
+        }
+    }
 
     impl SendInstr {
+        /// 5 bit field
+        pub const MAX_SKIP: u8 = 0x1f;
+
+        /// Preamble, data and skip default to 0, repeat to 1
+        pub const fn builder(send: SendParts) -> SendInstrBuilder {
+            SendInstrBuilder {
+                send,
+                skip: 0,
+                preamble: 0,
+                data: 0,
+                repeat: 1,
+            }
+        }
+        /// Check that [`SendInstr::to_command`] encodes every field unchanged
+        pub const fn validate(&self) -> Result<(), RangeError> {
+            if self.preamble.0 > Preamble::MAX {
+                Err(RangeError::Preamble(self.preamble.0))
+            } else if self.data.0 > Data::MAX {
+                Err(RangeError::Data(self.data.0))
+            } else if self.skip > Self::MAX_SKIP {
+                Err(RangeError::Skip(self.skip))
+            } else if self.repeat == 0 {
+                Err(RangeError::ZeroRepeat)
+            } else {
+                Ok(())
+            }
+        }
+
+        /// Out-of-range fields are masked, see [`SendInstr::validate`]
         pub const fn to_command(self) -> Command {
-            let body = (self.preamble.0 as u64) << 35 | (self.data.0 & 0x7ffff_ffff);
+            let body = ((self.preamble.0 & Preamble::MAX) as u64) << 35 | (self.data.0 & Data::MAX);
             let body = body.to_be_bytes();
             Command([
                 match self.send {
                     SendParts::Preamble => 0b110_00000,
                     SendParts::Data => 0b101_00000,
                     SendParts::All => 0b111_00000,
-                } | (self.skip & 0x1f),
+                } | (self.skip & Self::MAX_SKIP),
                 self.repeat,
                 body[2],
                 body[3],
@@ -85,7 +294,284 @@ pub mod ykq368 {
         }
     }
 
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+        Serialize,
+        Deserialize,
+        strum::EnumString,
+    )]
+    #[serde(rename_all = "snake_case")]
+    #[strum(serialize_all = "snake_case")]
+    pub enum Button {
+        Open,
+        Close,
+        Stop,
+        Lock,
+        Program,
+    }
+    impl Button {
+        pub const ALL: [Self; 5] = [
+            Self::Open,
+            Self::Close,
+            Self::Stop,
+            Self::Lock,
+            Self::Program,
+        ];
+        #[cfg(feature = "ykq368-frame")]
+        pub const fn code(self) -> u8 {
+            match self {
+                Self::Close => 0x1,
+                Self::Open => 0x2,
+                Self::Stop => 0x4,
+                Self::Lock => 0x8,
+                Self::Program => 0xf,
+            }
+        }
+        #[cfg(feature = "ykq368-frame")]
+        pub const fn from_code(code: u8) -> Option<Self> {
+            match code {
+                0x1 => Some(Self::Close),
+                0x2 => Some(Self::Open),
+                0x4 => Some(Self::Stop),
+                0x8 => Some(Self::Lock),
+                0xf => Some(Self::Program),
+                _ => None,
+            }
+        }
+    }
+
+    #[cfg(feature = "ykq368-frame")]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum FrameError {
+        Check { expected: u8, found: u8 },
+        UnknownButton(u8),
+        Channel(u8),
+    }
+    #[cfg(feature = "ykq368-frame")]
+    impl core::fmt::Display for FrameError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Self::Check { expected, found } => {
+                    write!(f, "check {found:#x} does not match {expected:#x}")
+                }
+                Self::UnknownButton(code) => write!(f, "unknown button code {code:#x}"),
+                Self::Channel(v) => {
+                    write!(f, "channel {v} exceeds {}", Ykq368Frame::CHANNEL_MAX)
+                }
+            }
+        }
+    }
+    #[cfg(feature = "ykq368-frame")]
+    impl core::error::Error for FrameError {}
+
+    /// Fields of the 35 bit data word, from most significant bit:
+    ///
+    /// | bits   | field   |
+    /// |--------|---------|
+    /// | 34..12 | address |
+    /// | 11..8  | channel |
+    /// | 7..4   | button  |
+    /// | 3..0   | check, XOR of all other nibbles |
+    ///
+    /// Experimental: field positions, button codes and the check are guessed and not yet
+    /// confirmed by codes captured from real remotes, so nothing sends frames built from it
+    /// unless the `ykq368-frame` feature is enabled.
+    #[cfg(feature = "ykq368-frame")]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Ykq368Frame {
+        /// 23 bit remote address
+        pub address: u32,
+        /// 4 bit channel
+        pub channel: u8,
+        pub button: Button,
+    }
+    #[cfg(feature = "ykq368-frame")]
+    impl Ykq368Frame {
+        pub const ADDRESS_MAX: u32 = 0x7f_ffff;
+        pub const CHANNEL_MAX: u8 = 0xf;
+
+        /// XOR of nibbles above the check field
+        const fn check(body: u64) -> u8 {
+            let mut v = body >> 4;
+            let mut ret = 0;
+            while v != 0 {
+                ret ^= (v & 0xf) as u8;
+                v >>= 4;
+            }
+            ret
+        }
+
+        pub const fn from_data(data: Data) -> Result<Self, FrameError> {
+            let v = data.0 & Data::MAX;
+            let expected = Self::check(v);
+            let found = (v & 0xf) as u8;
+            if expected != found {
+                return Err(FrameError::Check { expected, found });
+            }
+            let code = (v >> 4 & 0xf) as u8;
+            match Button::from_code(code) {
+                Some(button) => Ok(Self {
+                    address: (v >> 12) as u32,
+                    channel: (v >> 8 & 0xf) as u8,
+                    button,
+                }),
+                None => Err(FrameError::UnknownButton(code)),
+            }
+        }
+        /// Out-of-range address and channel are masked
+        pub const fn to_data(self) -> Data {
+            let body = ((self.address & Self::ADDRESS_MAX) as u64) << 12
+                | ((self.channel & Self::CHANNEL_MAX) as u64) << 8
+                | (self.button.code() as u64) << 4;
+            Data(body | Self::check(body) as u64)
+        }
+        /// Same remote and channel with another button
+        pub const fn with_button(self, button: Button) -> Self {
+            Self { button, ..self }
+        }
+        /// Nonzero address of a virtual remote, from a SplitMix64 step of `seed`
+        pub const fn address_from_seed(seed: u64) -> u32 {
+            let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^= z >> 31;
+            match (z >> 41) as u32 {
+                0 => Self::ADDRESS_MAX,
+                address => address,
+            }
+        }
+    }
+
+    /// Mirror of the FPGA `InstrConfig`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct InstrTiming {
+        pub preamble: PwmTiming,
+        pub preamble_wait: Duration,
+        pub data: PwmTiming,
+        pub data_wait: Duration,
+    }
+    impl InstrTiming {
+        pub const PREAMBLE_BITS: u32 = 13;
+        pub const DATA_BITS: u32 = 35;
+        /// Bits dropped from every other data frame, starting with the first
+        pub const ALTERNATE_SKIP: u32 = 2;
+    }
+    impl Default for InstrTiming {
+        fn default() -> Self {
+            Self {
+                preamble: PwmTiming {
+                    period: Duration::from_millis(1),
+                    high0: Duration::from_micros(470),
+                    high1: Duration::from_micros(200),
+                },
+                preamble_wait: Duration::from_micros(6700),
+                data: PwmTiming {
+                    period: Duration::from_millis(1),
+                    high0: Duration::from_micros(700),
+                    high1: Duration::from_micros(200),
+                },
+                data_wait: Duration::from_micros(6700),
+            }
+        }
+    }
+
+    impl SendInstr {
+        /// Bits sent in data frame `idx` of `0..repeat`, as `InstrProc` sets the encoder skip.
+        ///
+        /// Without `skip` this alternates between 33 and 35 bits, following the RTL which drops
+        /// 2 bits from even frames only. The `InstrProc` comment says 33/32, which the RTL does
+        /// not do.
+        pub const fn data_frame_bits(&self, idx: u8) -> u32 {
+            let mut skip = 0;
+            if idx & 1 == 0 {
+                skip += InstrTiming::ALTERNATE_SKIP;
+            }
+            if idx + 1 == self.repeat {
+                skip += (self.skip & Self::MAX_SKIP) as u32;
+            }
+            InstrTiming::DATA_BITS.saturating_sub(skip)
+        }
+
+        /// Time from the FPGA starting this instruction until it is ready for the next one.
+        /// A preamble-only instruction repeats the preamble, otherwise the preamble is sent
+        /// once before `repeat` data frames. Zero if the FPGA ignores the instruction.
+        pub fn airtime(&self, timing: &InstrTiming) -> Duration {
+            if self.repeat == 0 {
+                return Duration::ZERO;
+            }
+            let repeat = self.repeat as u32;
+            let preamble = timing.preamble.period * InstrTiming::PREAMBLE_BITS;
+            match self.send {
+                SendParts::Preamble => preamble * repeat + timing.preamble_wait * (repeat - 1),
+                SendParts::Data | SendParts::All => {
+                    let bits: u32 = (0..self.repeat).map(|i| self.data_frame_bits(i)).sum();
+                    let data = timing.data.period * bits + timing.data_wait * (repeat - 1);
+                    match self.send {
+                        SendParts::All => preamble + timing.preamble_wait + data,
+                        _ => data,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reserved bit pattern in [`Command`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DecodeError {
+        /// b63 is always set by [`SendInstr::to_command`]
+        MissingMarker,
+        /// Neither b62 nor b61 is set, the FPGA exits the endpoint on such commands
+        NothingToSend,
+        /// The FPGA ignores instructions without repeats
+        ZeroRepeat,
+    }
+    impl core::fmt::Display for DecodeError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str(match self {
+                Self::MissingMarker => "b63 not set",
+                Self::NothingToSend => "neither preamble nor data is sent",
+                Self::ZeroRepeat => "repeat is zero",
+            })
+        }
+    }
+    impl core::error::Error for DecodeError {}
+
+    impl TryFrom<Command> for SendInstr {
+        type Error = DecodeError;
+        /// Slice bit fields as the FPGA `Ykq368Endpoint` does
+        fn try_from(cmd: Command) -> Result<Self, Self::Error> {
+            let v = u64::from_be_bytes(cmd.0);
+            if v >> 63 == 0 {
+                return Err(DecodeError::MissingMarker);
+            }
+            let ret = Self {
+                send: match (v >> 62 & 1 != 0, v >> 61 & 1 != 0) {
+                    (true, true) => SendParts::All,
+                    (true, false) => SendParts::Preamble,
+                    (false, true) => SendParts::Data,
+                    (false, false) => return Err(DecodeError::NothingToSend),
+                },
+                skip: (v >> 56 & 0x1f) as u8,
+                repeat: (v >> 48) as u8,
+                preamble: Preamble((v >> 35 & 0x1fff) as u16),
+                data: Data(v & 0x7_ffff_ffff),
+            };
+            if ret.repeat == 0 {
+                return Err(DecodeError::ZeroRepeat);
+            }
+            Ok(ret)
+        }
+    }
+
+    #[cfg(feature = "std")]
     pub struct Ykq368Endpoint<'a, W>(&'a mut W);
+    #[cfg(feature = "std")]
     impl<'a, W: io::Write> Ykq368Endpoint<'a, W> {
         pub fn new(inner: &'a mut W) -> Self {
             Self(inner)
@@ -98,8 +584,26 @@ pub mod ykq368 {
         }
     }
 
+    /// Async counterpart of [`Ykq368Endpoint`]
+    #[cfg(feature = "tokio")]
+    pub struct AsyncYkq368Endpoint<'a, W>(&'a mut W);
+    #[cfg(feature = "tokio")]
+    impl<'a, W: tokio::io::AsyncWrite + Unpin> AsyncYkq368Endpoint<'a, W> {
+        pub fn new(inner: &'a mut W) -> Self {
+            Self(inner)
+        }
+        pub async fn send(&mut self, cmd: Command) -> io::Result<()> {
+            tokio::io::AsyncWriteExt::write_all(self.0, &cmd.0).await
+        }
+        pub async fn exit(self) -> io::Result<()> {
+            tokio::io::AsyncWriteExt::write_all(self.0, &[0; 8]).await
+        }
+    }
+
     #[cfg(test)]
     mod tests {
+        use proptest::prelude::*;
+
         use super::*;
 
         #[test]
@@ -116,5 +620,191 @@ pub mod ykq368 {
                 Command([0xe0, 0x01, 0x00, 0x3b, 0x12, 0x34, 0x56, 0x78])
             )
         }
+
+        #[test]
+        fn deserialize() {
+            use serde::de::value::{BytesDeserializer, Error, StrDeserializer};
+
+            let preamble =
+                |v: &'static str| Preamble::deserialize(StrDeserializer::<Error>::new(v));
+            assert_eq!(preamble("0x0007"), Ok(Preamble(7)));
+            assert_eq!(preamble("1fff"), Ok(Preamble(0x1fff)));
+            assert!(preamble("10000").is_err());
+            assert!(preamble("+7").is_err());
+            assert!(preamble("2000").is_err());
+            assert_eq!(
+                Data::deserialize(BytesDeserializer::<Error>::new(&[
+                    3, 0x12, 0x34, 0x56, 0x78
+                ])),
+                Ok(Data(0x312345678))
+            );
+            assert!(Data::deserialize(BytesDeserializer::<Error>::new(&[0x12, 0x34])).is_err());
+            assert!(Data::deserialize(BytesDeserializer::<Error>::new(&[8, 0, 0, 0, 0])).is_err());
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn deserialize_std() {
+            use serde::de::{value::Error, IntoDeserializer};
+
+            assert_eq!(
+                Preamble::deserialize(vec![0u8, 7].into_deserializer()),
+                Ok::<_, Error>(Preamble(7))
+            );
+
+            let instr = SendInstr {
+                send: SendParts::Data,
+                skip: 2,
+                repeat: 3,
+                preamble: Preamble(0x123),
+                data: Data(0x4_5678_9abc),
+            };
+            let mut buf = Vec::new();
+            ciborium::into_writer(&instr, &mut buf).unwrap();
+            assert_eq!(
+                ciborium::from_reader::<SendInstr, _>(&buf[..]).unwrap(),
+                instr
+            );
+        }
+
+        #[test]
+        fn checked_fields() {
+            assert_eq!(parse_u16("0x1555"), Ok(0x1555));
+            assert_eq!(parse_u16("4096"), Ok(4096));
+            assert!(parse_u16("0x10000").is_err());
+            assert_eq!(parse_u64("0x312345678"), Ok(0x312345678));
+            assert_eq!(parse_u64("42"), Ok(42));
+            assert!(parse_u64("0x").is_err());
+            assert_eq!(Preamble::new(0x2007), Err(RangeError::Preamble(0x2007)));
+            assert_eq!(
+                Data::new(0x8_0000_0000),
+                Err(RangeError::Data(0x8_0000_0000))
+            );
+            assert_eq!(
+                SendInstr::builder(SendParts::All)
+                    .preamble(0x007)
+                    .data(0x312345678)
+                    .build()
+                    .map(SendInstr::to_command),
+                Ok(Command([0xe0, 0x01, 0x00, 0x3b, 0x12, 0x34, 0x56, 0x78]))
+            );
+            assert_eq!(
+                SendInstr::builder(SendParts::Data).skip(32).build(),
+                Err(RangeError::Skip(32))
+            );
+            assert_eq!(
+                SendInstr::builder(SendParts::Data).repeat(0).build(),
+                Err(RangeError::ZeroRepeat)
+            );
+        }
+
+        #[cfg(feature = "ykq368-frame")]
+        #[test]
+        fn frame_fields() {
+            let frame = Ykq368Frame {
+                address: 0x123456,
+                channel: 3,
+                button: Button::Open,
+            };
+            // nibbles 1 2 3 4 5 6 3 2 xor to 6
+            assert_eq!(frame.to_data(), Data(0x1_2345_6326));
+            assert_eq!(Ykq368Frame::from_data(frame.to_data()), Ok(frame));
+            for button in Button::ALL {
+                let data = frame.with_button(button).to_data();
+                assert_eq!(Ykq368Frame::from_data(data).map(|f| f.button), Ok(button));
+            }
+            assert_eq!(
+                Ykq368Frame::from_data(Data(0x1_2345_6327)),
+                Err(FrameError::Check {
+                    expected: 6,
+                    found: 7
+                })
+            );
+        }
+
+        #[test]
+        fn airtime() {
+            let timing = InstrTiming::default();
+            let instr = SendInstr::builder(SendParts::All)
+                .skip(1)
+                .repeat(3)
+                .build()
+                .unwrap();
+            assert_eq!(
+                (0..3).map(|i| instr.data_frame_bits(i)).collect::<Vec<_>>(),
+                [33, 35, 32]
+            );
+            // 13 + 6.7 + 33 + 6.7 + 35 + 6.7 + 32
+            assert_eq!(instr.airtime(&timing), Duration::from_micros(133_100));
+            let instr = SendInstr {
+                send: SendParts::Preamble,
+                repeat: 2,
+                ..instr
+            };
+            assert_eq!(instr.airtime(&timing), Duration::from_micros(32_700));
+            let instr = SendInstr {
+                send: SendParts::Data,
+                repeat: 1,
+                skip: 0,
+                ..instr
+            };
+            assert_eq!(instr.airtime(&timing), Duration::from_millis(33));
+        }
+
+        fn send_parts() -> impl Strategy<Value = SendParts> {
+            prop_oneof![
+                Just(SendParts::Preamble),
+                Just(SendParts::Data),
+                Just(SendParts::All)
+            ]
+        }
+        prop_compose! {
+            fn send_instr()(
+                send in send_parts(),
+                skip in 0..32u8,
+                repeat in 1..=255u8,
+                preamble in 0..0x2000u16,
+                data in 0..0x8_0000_0000u64,
+            ) -> SendInstr {
+                SendInstr { send, skip, preamble: Preamble(preamble), data: Data(data), repeat }
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn decode_encoded(instr in send_instr()) {
+                prop_assert_eq!(SendInstr::try_from(instr.to_command()), Ok(instr));
+            }
+
+            #[test]
+            fn encode_decoded(cmd in any::<[u8; 8]>()) {
+                if let Ok(instr) = SendInstr::try_from(Command(cmd)) {
+                    prop_assert_eq!(instr.to_command(), Command(cmd));
+                }
+            }
+        }
+
+        #[test]
+        fn header_exhaustive() {
+            let body = [0xa5, 0x5a, 0xc3, 0x3c, 0x96, 0x69];
+            for header in 0..=0xffffu16 {
+                let [b0, b1] = header.to_be_bytes();
+                let cmd = Command([b0, b1, body[0], body[1], body[2], body[3], body[4], body[5]]);
+                match SendInstr::try_from(cmd) {
+                    Ok(instr) => assert_eq!(instr.to_command(), cmd),
+                    Err(e) => assert_eq!(
+                        Some(e),
+                        [
+                            (b0 & 0x80 == 0).then_some(DecodeError::MissingMarker),
+                            (b0 & 0x60 == 0).then_some(DecodeError::NothingToSend),
+                            (b1 == 0).then_some(DecodeError::ZeroRepeat),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .next()
+                    ),
+                }
+            }
+        }
     }
 }
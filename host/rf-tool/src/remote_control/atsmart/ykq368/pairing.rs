@@ -0,0 +1,196 @@
+//! Virtual remotes and the program button sequence adding them to a motor.
+//!
+//! Experimental and behind the `ykq368-frame` feature: the codes use the guessed
+//! [`Ykq368Frame`] layout, and [`PAIRING`] has not been checked against a motor either.
+
+use std::{io, time::Duration};
+
+use super::{
+    remotes::Remote, Button, FrameError, InstrTiming, Preamble, SendInstr, Ykq368Endpoint,
+    Ykq368Frame,
+};
+
+/// Remote with codes for every button, sharing an address drawn from `seed`
+pub fn generate(
+    seed: u64,
+    channel: u8,
+    preamble: Option<Preamble>,
+    repeat: u8,
+) -> Result<Remote, FrameError> {
+    if channel > Ykq368Frame::CHANNEL_MAX {
+        return Err(FrameError::Channel(channel));
+    }
+    let frame = Ykq368Frame {
+        address: Ykq368Frame::address_from_seed(seed),
+        channel,
+        button: Button::Program,
+    };
+    Ok(Remote {
+        preamble,
+        buttons: Button::ALL
+            .into_iter()
+            .map(|button| (button, frame.with_button(button).to_data()))
+            .collect(),
+        repeat,
+        skip: 0,
+        derive: false,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// A remote the motor already reacts to
+    Paired,
+    /// The remote being added
+    New,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PairingStep {
+    pub source: Source,
+    pub button: Button,
+    /// Minimum airtime, repeats are added to long presses
+    pub hold: Duration,
+    /// What the motor does in response
+    pub expect: &'static str,
+}
+
+/// Usual pattern of these tubular motors: hold program on a paired remote, press it again,
+/// then press program on the new remote. Check the motor manual if it does not jog.
+pub const PAIRING: [PairingStep; 3] = [
+    PairingStep {
+        source: Source::Paired,
+        button: Button::Program,
+        hold: Duration::from_secs(2),
+        expect: "motor jogs once",
+    },
+    PairingStep {
+        source: Source::Paired,
+        button: Button::Program,
+        hold: Duration::ZERO,
+        expect: "motor jogs once",
+    },
+    PairingStep {
+        source: Source::New,
+        button: Button::Program,
+        hold: Duration::ZERO,
+        expect: "motor jogs twice",
+    },
+];
+
+impl PairingStep {
+    /// `None` if the remote has no code for the button
+    pub fn instr(&self, remote: &Remote, timing: &InstrTiming) -> Option<SendInstr> {
+        let mut instr = remote.instr(self.button)?;
+        while instr.airtime(timing) < self.hold && instr.repeat < u8::MAX {
+            instr.repeat += 1;
+        }
+        Some(instr)
+    }
+}
+
+/// Send [`PAIRING`], `confirm` is called after each step and pairing stops once it returns
+/// false. Returns whether all steps were confirmed.
+pub fn pair<W: io::Write>(
+    endpoint: &mut Ykq368Endpoint<W>,
+    paired: &Remote,
+    new: &Remote,
+    timing: &InstrTiming,
+    mut confirm: impl FnMut(&PairingStep, &SendInstr) -> io::Result<bool>,
+) -> io::Result<bool> {
+    for step in &PAIRING {
+        let remote = match step.source {
+            Source::Paired => paired,
+            Source::New => new,
+        };
+        let instr = step.instr(remote, timing).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?} remote has no code for {:?}", step.source, step.button),
+            )
+        })?;
+        instr
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        endpoint.send(instr.to_command())?;
+        if !confirm(step, &instr)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::Data, *};
+
+    #[test]
+    fn generated_codes() {
+        let remote = generate(42, 1, None, 3).unwrap();
+        assert_eq!(remote, generate(42, 1, None, 3).unwrap());
+        assert_ne!(remote.buttons, generate(43, 1, None, 3).unwrap().buttons);
+        assert_eq!(generate(42, 16, None, 3), Err(FrameError::Channel(16)));
+        let frames: Vec<_> = Button::ALL
+            .map(|button| Ykq368Frame::from_data(remote.buttons[&button]).unwrap())
+            .into();
+        assert!(frames
+            .iter()
+            .all(|f| f.address == frames[0].address && f.channel == 1));
+        assert_eq!(
+            frames.iter().map(|f| f.button).collect::<Vec<_>>(),
+            Button::ALL
+        );
+    }
+
+    fn program_only(data: u64) -> Remote {
+        Remote {
+            preamble: None,
+            buttons: [(Button::Program, Data(data))].into(),
+            repeat: 3,
+            skip: 0,
+            derive: false,
+        }
+    }
+
+    #[test]
+    fn pairing_sequence() {
+        let paired = program_only(0x1_2345_60f0);
+        let new = program_only(0x2_3456_70f0);
+        let timing = InstrTiming::default();
+        let mut buf = Vec::new();
+        let mut confirmed = Vec::new();
+        assert!(pair(
+            &mut Ykq368Endpoint::new(&mut buf),
+            &paired,
+            &new,
+            &timing,
+            |step, instr| {
+                confirmed.push((step.source, *instr));
+                Ok(true)
+            }
+        )
+        .unwrap());
+        assert_eq!(buf.len(), 3 * 8);
+        let [(_, hold), (_, press), (source, new_press)] = confirmed[..] else {
+            panic!("expected 3 steps")
+        };
+        assert!(hold.airtime(&timing) >= Duration::from_secs(2));
+        assert_eq!(press.repeat, 3);
+        assert_eq!(source, Source::New);
+        assert_eq!(new_press.data, new.buttons[&Button::Program]);
+
+        let mut steps = 0;
+        assert!(!pair(
+            &mut Ykq368Endpoint::new(&mut Vec::new()),
+            &paired,
+            &new,
+            &timing,
+            |_, _| {
+                steps += 1;
+                Ok(false)
+            }
+        )
+        .unwrap());
+        assert_eq!(steps, 1);
+    }
+}
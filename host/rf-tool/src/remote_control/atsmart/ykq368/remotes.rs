@@ -0,0 +1,306 @@
+//! Named remotes and groups of them, kept in a TOML or YAML file such as
+//!
+//! ```toml
+//! [remotes.living-room]
+//! preamble = "0x1555"
+//! repeat = 5
+//! buttons = { open = "0x123456326", close = "0x123456315", stop = "0x123456340" }
+//! # lock code from the experimental frame layout, see Remote::derive
+//! derive = true
+//!
+//! [groups]
+//! all-blinds = ["living-room", "bedroom"]
+//! ```
+
+use std::collections::BTreeMap;
+#[cfg(feature = "remotes")]
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ykq368-frame")]
+use super::Ykq368Frame;
+use super::{Button, Data, Preamble, RangeError, SendInstr, SendParts};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remote {
+    /// Sent once before the data frames if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preamble: Option<Preamble>,
+    pub buttons: BTreeMap<Button, Data>,
+    #[serde(default = "Remote::default_repeat")]
+    pub repeat: u8,
+    #[serde(default)]
+    pub skip: u8,
+    /// Derive codes of missing buttons from another button with the guessed `Ykq368Frame`
+    /// layout. Only done with the `ykq368-frame` feature.
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    pub derive: bool,
+}
+impl Remote {
+    const fn default_repeat() -> u8 {
+        3
+    }
+
+    /// Code of `button`, derived as in [`Remote::derived`] if missing and `derive` is set
+    pub fn data(&self, button: Button) -> Option<Data> {
+        self.buttons
+            .get(&button)
+            .copied()
+            .or_else(|| self.derived(button))
+    }
+    /// Code of `button` from the first valid [`Ykq368Frame`] of another button, if it is
+    /// missing and `derive` is set
+    #[cfg(feature = "ykq368-frame")]
+    pub fn derived(&self, button: Button) -> Option<Data> {
+        if !self.derive || self.buttons.contains_key(&button) {
+            return None;
+        }
+        self.buttons.values().find_map(|data| {
+            Ykq368Frame::from_data(*data)
+                .ok()
+                .map(|frame| frame.with_button(button).to_data())
+        })
+    }
+    /// Always `None` without the `ykq368-frame` feature
+    #[cfg(not(feature = "ykq368-frame"))]
+    pub fn derived(&self, _button: Button) -> Option<Data> {
+        None
+    }
+    /// `None` if there is no code for `button`, fields are not checked
+    pub fn instr(&self, button: Button) -> Option<SendInstr> {
+        let data = self.data(button)?;
+        Some(SendInstr {
+            send: match self.preamble {
+                Some(_) => SendParts::All,
+                None => SendParts::Data,
+            },
+            skip: self.skip,
+            preamble: self.preamble.unwrap_or(Preamble(0)),
+            data,
+            repeat: self.repeat,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemotesFile {
+    #[serde(default)]
+    pub remotes: BTreeMap<String, Remote>,
+    /// Members are remote names, sent in order
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemotesError {
+    UnknownName(String),
+    MissingButton { remote: String, button: Button },
+    Range { remote: String, error: RangeError },
+}
+impl std::fmt::Display for RemotesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownName(name) => write!(f, "no remote or group named {name}"),
+            Self::MissingButton { remote, button } => {
+                write!(f, "remote {remote} has no code for {button:?}")
+            }
+            Self::Range { remote, error } => write!(f, "remote {remote}: {error}"),
+        }
+    }
+}
+impl std::error::Error for RemotesError {}
+
+impl RemotesFile {
+    /// YAML for `.yaml` and `.yml` files, TOML otherwise
+    #[cfg(feature = "remotes")]
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        if is_yaml(path) {
+            serde_yaml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        } else {
+            toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+    }
+    /// Format chosen as in [`RemotesFile::load`]
+    #[cfg(feature = "remotes")]
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = if is_yaml(path) {
+            serde_yaml::to_string(self)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        } else {
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        };
+        fs::write(path, text)
+    }
+    /// Write remote `name` to the end of a TOML file, keeping the rest of it and its comments
+    /// as they are. YAML cannot be appended to like this, so it is rewritten by
+    /// [`RemotesFile::save`] and loses its comments.
+    #[cfg(feature = "remotes")]
+    pub fn save_remote(&self, path: &Path, name: &str) -> io::Result<()> {
+        if is_yaml(path) {
+            return self.save(path);
+        }
+        let remote = self.remotes.get(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                RemotesError::UnknownName(name.to_owned()),
+            )
+        })?;
+        let table = Self {
+            remotes: [(name.to_owned(), remote.clone())].into(),
+            groups: BTreeMap::new(),
+        };
+        let text =
+            toml::to_string(&table).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(format!("\n{text}").as_bytes())
+    }
+
+    /// Remotes of `name`, which is a remote or, if there is none, a group
+    pub fn resolve<'s>(
+        &'s self,
+        name: &'s str,
+    ) -> Result<Vec<(&'s str, &'s Remote)>, RemotesError> {
+        let names = match (self.remotes.contains_key(name), self.groups.get(name)) {
+            (false, Some(members)) => members.iter().map(String::as_str).collect(),
+            _ => vec![name],
+        };
+        names
+            .into_iter()
+            .map(|name| match self.remotes.get(name) {
+                Some(remote) => Ok((name, remote)),
+                None => Err(RemotesError::UnknownName(name.to_owned())),
+            })
+            .collect()
+    }
+    /// Checked instructions pressing `button` on every remote of `name`
+    pub fn instrs<'s>(
+        &'s self,
+        name: &'s str,
+        button: Button,
+    ) -> Result<Vec<(&'s str, SendInstr)>, RemotesError> {
+        self.resolve(name)?
+            .into_iter()
+            .map(|(name, remote)| {
+                let instr = remote
+                    .instr(button)
+                    .ok_or_else(|| RemotesError::MissingButton {
+                        remote: name.to_owned(),
+                        button,
+                    })?;
+                instr.validate().map_err(|error| RemotesError::Range {
+                    remote: name.to_owned(),
+                    error,
+                })?;
+                Ok((name, instr))
+            })
+            .collect()
+    }
+}
+
+/// Whether `path` is loaded and saved as YAML rather than TOML
+#[cfg(feature = "remotes")]
+pub fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml" | "yml")
+    )
+}
+
+#[cfg(all(test, feature = "remotes"))]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+[remotes.living-room]
+preamble = "0x1555"
+repeat = 5
+buttons = { open = "0x123456326" }
+derive = true
+
+[remotes.bedroom]
+skip = 1
+buttons = { open = "0x000001221", stop = "0x000001247" }
+
+[groups]
+all-blinds = ["living-room", "bedroom"]
+"#;
+
+    #[test]
+    fn load_and_resolve() {
+        let file: RemotesFile = toml::from_str(TOML).unwrap();
+        let yaml = serde_yaml::to_string(&file).unwrap();
+        assert!(yaml.contains("open: '0x123456326'"), "{yaml}");
+        assert_eq!(serde_yaml::from_str::<RemotesFile>(&yaml).unwrap(), file);
+        assert_eq!(
+            toml::from_str::<RemotesFile>(&toml::to_string(&file).unwrap()).unwrap(),
+            file
+        );
+
+        let instrs = file.instrs("all-blinds", Button::Open).unwrap();
+        assert_eq!(
+            instrs.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            ["living-room", "bedroom"]
+        );
+        assert_eq!(instrs[0].1.send, SendParts::All);
+        assert_eq!(instrs[0].1.repeat, 5);
+        assert_eq!(instrs[1].1.send, SendParts::Data);
+        assert_eq!(instrs[1].1.skip, 1);
+        assert_eq!(instrs[1].1.repeat, 3);
+
+        #[cfg(feature = "ykq368-frame")]
+        {
+            // derived from the open code
+            let close = file.instrs("living-room", Button::Close).unwrap();
+            assert_eq!(close[0].1.data, Data(0x1_2345_6315));
+            assert_eq!(
+                file.remotes["living-room"].derived(Button::Close),
+                Some(Data(0x1_2345_6315))
+            );
+            assert_eq!(file.remotes["living-room"].derived(Button::Open), None);
+        }
+        #[cfg(not(feature = "ykq368-frame"))]
+        assert_eq!(
+            file.instrs("living-room", Button::Close),
+            Err(RemotesError::MissingButton {
+                remote: "living-room".to_owned(),
+                button: Button::Close
+            })
+        );
+        // bedroom does not opt in
+        assert_eq!(
+            file.instrs("bedroom", Button::Close),
+            Err(RemotesError::MissingButton {
+                remote: "bedroom".to_owned(),
+                button: Button::Close
+            })
+        );
+        assert_eq!(
+            file.instrs("kitchen", Button::Open),
+            Err(RemotesError::UnknownName("kitchen".to_owned()))
+        );
+    }
+
+    #[test]
+    fn save_remote_appends() {
+        let path = std::env::temp_dir().join(format!("remotes-{}.toml", std::process::id()));
+        fs::write(&path, format!("# blinds{TOML}")).unwrap();
+        let mut file = RemotesFile::load(&path).unwrap();
+        let kitchen = file.remotes["bedroom"].clone();
+        file.remotes.insert("kitchen".to_owned(), kitchen);
+        file.save_remote(&path, "kitchen").unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let loaded = RemotesFile::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(text.starts_with("# blinds"), "{text}");
+        assert_eq!(loaded.unwrap(), file);
+    }
+}
@@ -0,0 +1,216 @@
+//! Timed sequences of instructions over [`Ykq368Endpoint`]
+
+use std::{
+    io,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use super::{InstrTiming, SendInstr, Ykq368Endpoint};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    /// From the start of the previous step, or of the timeline for the first one
+    pub delay: Duration,
+    pub instr: SendInstr,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    pub steps: Vec<Step>,
+    /// Sent when the timeline is cancelled before its last step
+    pub on_cancel: Option<SendInstr>,
+}
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn then(mut self, delay: Duration, instr: SendInstr) -> Self {
+        self.steps.push(Step { delay, instr });
+        self
+    }
+    pub fn on_cancel(self, instr: SendInstr) -> Self {
+        Self {
+            on_cancel: Some(instr),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sent {
+    /// Index into [`Timeline::steps`], `None` for [`Timeline::on_cancel`]
+    pub step: Option<usize>,
+    /// Offsets from the start of the run
+    pub scheduled: Duration,
+    pub written: Duration,
+    pub airtime: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub start: Instant,
+    pub sent: Vec<Sent>,
+    pub cancelled: bool,
+}
+
+/// Stops the [`Scheduler`] it was created from, also usable from other threads
+#[derive(Debug, Clone)]
+pub struct CancelHandle(mpsc::Sender<()>);
+impl CancelHandle {
+    pub fn cancel(&self) {
+        // the scheduler keeps its receiver, so this only fails after it is dropped
+        let _ = self.0.send(());
+    }
+}
+
+/// Writes each instruction once the previous one has finished on air, so it is never queued
+/// in the FPGA and cancelling takes effect after at most one airtime
+pub struct Scheduler<'a, W> {
+    endpoint: Ykq368Endpoint<'a, W>,
+    timing: InstrTiming,
+    cancel_tx: mpsc::Sender<()>,
+    cancel_rx: mpsc::Receiver<()>,
+}
+impl<'a, W: io::Write> Scheduler<'a, W> {
+    pub fn new(endpoint: Ykq368Endpoint<'a, W>, timing: InstrTiming) -> Self {
+        let (cancel_tx, cancel_rx) = mpsc::channel();
+        Self {
+            endpoint,
+            timing,
+            cancel_tx,
+            cancel_rx,
+        }
+    }
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle(self.cancel_tx.clone())
+    }
+    pub fn into_endpoint(self) -> Ykq368Endpoint<'a, W> {
+        self.endpoint
+    }
+
+    /// Sleep until `deadline`, returns true if cancelled before
+    fn wait_until(&self, deadline: Instant) -> bool {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.cancel_rx.recv_timeout(timeout) {
+            Ok(()) => true,
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => unreachable!("sender is kept in self"),
+        }
+    }
+    fn send(
+        &mut self,
+        start: Instant,
+        step: Option<usize>,
+        scheduled: Duration,
+        instr: SendInstr,
+    ) -> io::Result<Sent> {
+        let written = start.elapsed();
+        self.endpoint.send(instr.to_command())?;
+        Ok(Sent {
+            step,
+            scheduled,
+            written,
+            airtime: instr.airtime(&self.timing),
+        })
+    }
+
+    /// Cancellation requested before this call is ignored
+    pub fn run(&mut self, timeline: &Timeline) -> io::Result<Report> {
+        while self.cancel_rx.try_recv().is_ok() {}
+        let start = Instant::now();
+        let mut report = Report {
+            start,
+            sent: Vec::with_capacity(timeline.steps.len()),
+            cancelled: false,
+        };
+        let mut scheduled = Duration::ZERO;
+        let mut idle = Duration::ZERO;
+        for (idx, step) in timeline.steps.iter().enumerate() {
+            scheduled += step.delay;
+            if self.wait_until(start + scheduled.max(idle)) {
+                report.cancelled = true;
+                break;
+            }
+            let sent = self.send(start, Some(idx), scheduled, step.instr)?;
+            idle = sent.written + sent.airtime;
+            report.sent.push(sent);
+        }
+        if report.cancelled {
+            if let Some(instr) = timeline.on_cancel {
+                // the FPGA queues it behind the instruction on air
+                let sent = self.send(start, None, start.elapsed(), instr)?;
+                report.sent.push(sent);
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::SendParts;
+    use super::*;
+
+    fn instr(repeat: u8) -> SendInstr {
+        SendInstr::builder(SendParts::Data)
+            .data(0x1_2345_6326)
+            .repeat(repeat)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn paced_by_airtime() {
+        let mut buf = Vec::new();
+        let mut scheduler = Scheduler::new(Ykq368Endpoint::new(&mut buf), InstrTiming::default());
+        let report = scheduler
+            .run(
+                &Timeline::new()
+                    .then(Duration::ZERO, instr(2))
+                    .then(Duration::ZERO, instr(1))
+                    .then(Duration::from_millis(100), instr(1)),
+            )
+            .unwrap();
+        assert!(!report.cancelled);
+        let [a, b, c] = report.sent[..] else {
+            panic!("expected 3 sends, got {:?}", report.sent)
+        };
+        assert_eq!(a.airtime, Duration::from_micros(74_700));
+        assert!(b.written >= a.written + a.airtime);
+        assert_eq!(c.scheduled, Duration::from_millis(100));
+        assert!(c.written >= c.scheduled);
+        drop(scheduler);
+        assert_eq!(buf.len(), 3 * 8);
+    }
+
+    #[test]
+    fn cancel_sends_stop() {
+        let mut buf = Vec::new();
+        let stop = instr(3);
+        let report = std::thread::scope(|s| {
+            let mut scheduler =
+                Scheduler::new(Ykq368Endpoint::new(&mut buf), InstrTiming::default());
+            let handle = scheduler.cancel_handle();
+            s.spawn(move || {
+                std::thread::sleep(Duration::from_millis(20));
+                handle.cancel();
+            });
+            scheduler
+                .run(
+                    &Timeline::new()
+                        .then(Duration::ZERO, instr(1))
+                        .then(Duration::from_secs(10), stop)
+                        .on_cancel(stop),
+                )
+                .unwrap()
+        });
+        assert!(report.cancelled);
+        assert_eq!(
+            report.sent.iter().map(|s| s.step).collect::<Vec<_>>(),
+            [Some(0), None]
+        );
+        assert!(report.sent[1].written < Duration::from_secs(1));
+        assert_eq!(buf[8..], stop.to_command().0);
+    }
+}
@@ -0,0 +1,295 @@
+//! Pulses the FPGA `InstrProc`/`PwmEncoder` emit for a [`SendInstr`]
+
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+pub use crate::remote_control::pwm::write_packed;
+pub use crate::remote_control::pwm::Edge;
+use crate::remote_control::pwm::{self, decode_bits, Pulse, PulseError, RemoteProtocol};
+
+use super::{Data, InstrTiming, Preamble, PwmTiming, SendInstr, SendParts};
+
+/// Low `bits` bits of `value` sent MSB first, each bit high from the start of its period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub start: Duration,
+    pub timing: PwmTiming,
+    pub value: u64,
+    pub bits: u32,
+}
+impl Frame {
+    pub fn duration(&self) -> Duration {
+        self.timing.period * self.bits
+    }
+    pub fn end(&self) -> Duration {
+        self.start + self.duration()
+    }
+    pub fn bit(&self, idx: u32) -> bool {
+        self.value >> (self.bits - 1 - idx) & 1 != 0
+    }
+    pub fn pulses(self) -> impl Iterator<Item = Pulse> {
+        (0..self.bits).map(move |idx| self.timing.pulse(self.bit(idx)))
+    }
+    pub fn edges(self) -> impl Iterator<Item = Edge> {
+        pwm::edges(self.start, self.pulses())
+    }
+}
+
+impl SendInstr {
+    /// Frames in sending order, the last one ends after [`SendInstr::airtime`]. Out-of-range
+    /// fields are masked as in [`SendInstr::to_command`].
+    pub fn frames(&self, timing: &InstrTiming) -> impl Iterator<Item = Frame> {
+        let (instr, timing) = (*self, *timing);
+        let (preambles, data) = match instr.send {
+            SendParts::Preamble => (instr.repeat, 0),
+            SendParts::Data => (0, instr.repeat),
+            SendParts::All => (instr.repeat.min(1), instr.repeat),
+        };
+        let preamble = (0..preambles).map(move |_| {
+            (
+                timing.preamble,
+                (instr.preamble.0 & Preamble::MAX) as u64,
+                InstrTiming::PREAMBLE_BITS,
+                timing.preamble_wait,
+            )
+        });
+        let data = (0..data).map(move |idx| {
+            let bits = instr.data_frame_bits(idx);
+            (
+                timing.data,
+                (instr.data.0 & Data::MAX) >> (InstrTiming::DATA_BITS - bits),
+                bits,
+                timing.data_wait,
+            )
+        });
+        preamble
+            .chain(data)
+            .scan(Duration::ZERO, |start, (timing, value, bits, wait)| {
+                let frame = Frame {
+                    start: *start,
+                    timing,
+                    value,
+                    bits,
+                };
+                *start = frame.end() + wait;
+                Some(frame)
+            })
+    }
+    pub fn edges(&self, timing: &InstrTiming) -> impl Iterator<Item = Edge> {
+        self.frames(timing).flat_map(Frame::edges)
+    }
+}
+
+/// Data frame as `InstrProc` sends it: the top `bits` bits of `data`, MSB first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataFrame {
+    /// Bits past `bits` are not sent and zero after decoding
+    pub data: Data,
+    pub bits: u32,
+}
+impl DataFrame {
+    /// Frame lengths `InstrProc` alternates between without `skip`
+    pub const BITS: [u32; 2] = [
+        InstrTiming::DATA_BITS - InstrTiming::ALTERNATE_SKIP,
+        InstrTiming::DATA_BITS,
+    ];
+}
+impl From<Data> for DataFrame {
+    fn from(data: Data) -> Self {
+        Self {
+            data,
+            bits: InstrTiming::DATA_BITS,
+        }
+    }
+}
+
+/// Data frames of 33 or 35 bits, as [`SendInstr::frames`] alternates them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ykq368Protocol {
+    pub timing: InstrTiming,
+}
+impl RemoteProtocol for Ykq368Protocol {
+    type Code = DataFrame;
+
+    fn timing(&self) -> PwmTiming {
+        self.timing.data
+    }
+    fn encode(&self, code: DataFrame) -> impl Iterator<Item = Pulse> {
+        let bits = code.bits.min(InstrTiming::DATA_BITS);
+        let frame = Frame {
+            start: Duration::ZERO,
+            timing: self.timing.data,
+            value: (code.data.0 & Data::MAX) >> (InstrTiming::DATA_BITS - bits),
+            bits,
+        };
+        let wait = self.timing.data_wait;
+        frame.pulses().enumerate().map(move |(idx, pulse)| {
+            if idx + 1 == bits as usize {
+                Pulse {
+                    low: pulse.low + wait,
+                    ..pulse
+                }
+            } else {
+                pulse
+            }
+        })
+    }
+    fn decode(&self, pulses: &[Pulse]) -> Result<DataFrame, PulseError> {
+        const SHORT: usize = DataFrame::BITS[0] as usize;
+        const FULL: usize = DataFrame::BITS[1] as usize;
+        let value = match pulses.len() {
+            SHORT => decode_bits::<SHORT>(&self.timing.data, pulses, 0)?,
+            FULL => decode_bits::<FULL>(&self.timing.data, pulses, 0)?,
+            found => {
+                return Err(PulseError::Count {
+                    expected: FULL,
+                    found,
+                })
+            }
+        };
+        let bits = pulses.len() as u32;
+        Ok(DataFrame {
+            data: Data(value << (InstrTiming::DATA_BITS - bits)),
+            bits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn frame_edges() {
+        let timing = InstrTiming::default();
+        let instr = SendInstr::builder(SendParts::All)
+            .preamble(0x1000)
+            .data(0x4_0000_0001)
+            .skip(1)
+            .repeat(2)
+            .build()
+            .unwrap();
+        let frames: Vec<_> = instr.frames(&timing).collect();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|f| (f.start.as_micros(), f.value, f.bits))
+                .collect::<Vec<_>>(),
+            [
+                (0, 0x1000, 13),
+                (19_700, 0x1_0000_0000, 33),
+                (59_400, 0x2_0000_0000, 34),
+            ]
+        );
+        let edges: Vec<_> = frames[1].edges().take(4).collect();
+        assert_eq!(
+            edges,
+            [
+                Edge {
+                    time: Duration::from_micros(19_700),
+                    high: true
+                },
+                Edge {
+                    time: Duration::from_micros(19_900),
+                    high: false
+                },
+                Edge {
+                    time: Duration::from_micros(20_700),
+                    high: true
+                },
+                Edge {
+                    time: Duration::from_micros(21_400),
+                    high: false
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn packed_samples() {
+        let edges = [
+            Edge {
+                time: Duration::from_micros(20),
+                high: true,
+            },
+            Edge {
+                time: Duration::from_micros(50),
+                high: false,
+            },
+        ];
+        let mut buf = Vec::new();
+        write_packed(
+            edges,
+            Duration::from_micros(10),
+            Duration::from_micros(100),
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(buf, [0b0011_1000, 0b0000_0000]);
+    }
+
+    #[test]
+    fn protocol_frame() {
+        let protocol = Ykq368Protocol::default();
+        let pulses: Vec<_> = protocol.encode(Data(0x1_2345_6326).into()).collect();
+        // last bit is 0
+        let last = pulses[pulses.len() - 1];
+        assert_eq!(
+            last.low,
+            Duration::from_micros(300) + protocol.timing.data_wait
+        );
+        assert_eq!(protocol.decode(&pulses), Ok(Data(0x1_2345_6326).into()));
+
+        // the frames of a data instruction split at the waits between them
+        let instr = SendInstr::builder(SendParts::Data)
+            .data(0x1_2345_6327)
+            .repeat(3)
+            .build()
+            .unwrap();
+        let timing = InstrTiming::default();
+        let end = instr.airtime(&timing);
+        let pulses: Vec<_> = pwm::pulses(instr.edges(&timing), end).collect();
+        let frames: Vec<_> = protocol
+            .frames(&pulses)
+            .map(|frame| protocol.decode(frame))
+            .collect();
+        let short = DataFrame {
+            data: Data(0x1_2345_6324),
+            bits: 33,
+        };
+        assert_eq!(
+            frames,
+            [Ok(short), Ok(Data(0x1_2345_6327).into()), Ok(short)]
+        );
+        assert_eq!(
+            protocol.decode(&pulses[..34]),
+            Err(PulseError::Count {
+                expected: 35,
+                found: 34
+            })
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn frames_fill_airtime(
+            send in prop_oneof![
+                Just(SendParts::Preamble),
+                Just(SendParts::Data),
+                Just(SendParts::All)
+            ],
+            skip in 0..32u8,
+            repeat in 1..=255u8,
+        ) {
+            let timing = InstrTiming::default();
+            let instr = SendInstr::builder(send).skip(skip).repeat(repeat).build().unwrap();
+            prop_assert_eq!(
+                instr.frames(&timing).last().map(|f| f.end()),
+                Some(instr.airtime(&timing))
+            );
+        }
+    }
+}
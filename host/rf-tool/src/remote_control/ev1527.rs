@@ -0,0 +1,126 @@
+//! EV1527 learning code, a sync pulse then a 20 bit ID and 4 data bits
+
+use core::time::Duration;
+
+use super::pwm::{decode_bits, Pulse, PulseError, PwmTiming, RemoteProtocol};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ev1527Code {
+    pub id: u32,
+    /// One bit per key
+    pub data: u8,
+}
+impl Ev1527Code {
+    pub const ID_MAX: u32 = 0xf_ffff;
+    pub const DATA_MAX: u8 = 0xf;
+
+    const fn to_bits(self) -> u64 {
+        ((self.id & Self::ID_MAX) as u64) << 4 | (self.data & Self::DATA_MAX) as u64
+    }
+}
+
+/// `unit` is the short pulse, 4 oscillator clocks; bits last 4 units and sync 32
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ev1527 {
+    pub unit: Duration,
+}
+impl Default for Ev1527 {
+    fn default() -> Self {
+        Self {
+            unit: Duration::from_micros(350),
+        }
+    }
+}
+impl Ev1527 {
+    const BITS: usize = 24;
+
+    pub fn sync(&self) -> Pulse {
+        Pulse {
+            high: self.unit,
+            low: self.unit * 31,
+        }
+    }
+}
+impl RemoteProtocol for Ev1527 {
+    type Code = Ev1527Code;
+
+    fn timing(&self) -> PwmTiming {
+        PwmTiming {
+            period: self.unit * 4,
+            high0: self.unit,
+            high1: self.unit * 3,
+        }
+    }
+    fn encode(&self, code: Ev1527Code) -> impl Iterator<Item = Pulse> {
+        let (timing, bits) = (self.timing(), code.to_bits());
+        core::iter::once(self.sync()).chain(
+            (0..Self::BITS).map(move |idx| timing.pulse(bits >> (Self::BITS - 1 - idx) & 1 != 0)),
+        )
+    }
+    fn decode(&self, pulses: &[Pulse]) -> Result<Ev1527Code, PulseError> {
+        if pulses.len() != Self::BITS + 1 {
+            return Err(PulseError::Count {
+                expected: Self::BITS + 1,
+                found: pulses.len(),
+            });
+        }
+        let timing = self.timing();
+        if timing.classify(pulses[0].high) != Some(false) || pulses[0].low < timing.period * 2 {
+            return Err(PulseError::Width(0));
+        }
+        let bits = decode_bits::<{ Self::BITS }>(&timing, pulses, 1)?;
+        Ok(Ev1527Code {
+            id: (bits >> 4) as u32,
+            data: (bits & 0xf) as u8,
+        })
+    }
+    /// Frames start at the sync pulse, so the long low after the last bit of a transmission
+    /// does not end a frame
+    fn frames<'p>(&self, pulses: &'p [Pulse]) -> impl Iterator<Item = &'p [Pulse]> {
+        let gap = self.timing().period * 2;
+        let mut rest = pulses;
+        core::iter::from_fn(move || {
+            let start = rest.iter().position(|pulse| pulse.low > gap)?;
+            let frame = &rest[start..rest.len().min(start + Self::BITS + 1)];
+            rest = &rest[start + frame.len()..];
+            Some(frame)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let ev1527 = Ev1527::default();
+        let code = Ev1527Code {
+            id: 0xa5c3e,
+            data: 0b0100,
+        };
+        let pulses: Vec<_> = ev1527.encode(code).collect();
+        assert_eq!(pulses.len(), 25);
+        assert_eq!(pulses[0], ev1527.sync());
+        // first ID bit is 1
+        assert_eq!(pulses[1].high, Duration::from_micros(1050));
+        assert_eq!(ev1527.decode(&pulses), Ok(code));
+        assert_eq!(
+            ev1527.decode(&pulses[1..]),
+            Err(PulseError::Count {
+                expected: 25,
+                found: 24
+            })
+        );
+
+        let mut repeated: Vec<_> = pulses.iter().chain(&pulses).copied().collect();
+        repeated.last_mut().unwrap().low = Duration::from_millis(50);
+        assert_eq!(
+            ev1527
+                .frames(&repeated)
+                .map(|frame| ev1527.decode(frame))
+                .collect::<Vec<_>>(),
+            [Ok(code), Ok(code)]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Princeton Technology PT2262 tri-state code, 12 code bits of two pulses each then a sync
+//! pulse
+
+use core::time::Duration;
+
+use super::pwm::{Pulse, PulseError, PwmTiming, RemoteProtocol};
+
+/// PT2262 address and data pins are tied low, high or left floating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trit {
+    Zero,
+    One,
+    Float,
+}
+impl Trit {
+    const fn bits(self) -> [bool; 2] {
+        match self {
+            Self::Zero => [false, false],
+            Self::One => [true, true],
+            Self::Float => [false, true],
+        }
+    }
+    const fn from_bits(bits: [bool; 2]) -> Option<Self> {
+        match bits {
+            [false, false] => Some(Self::Zero),
+            [true, true] => Some(Self::One),
+            [false, true] => Some(Self::Float),
+            [true, false] => None,
+        }
+    }
+}
+
+/// Usually 8 address bits, then 4 data bits which cannot float on PT2262 data pins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pt2262Code(pub [Trit; 12]);
+
+/// `unit` is the short pulse, 4 oscillator clocks; pulses last 4 units and sync 32
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pt2262 {
+    pub unit: Duration,
+}
+impl Default for Pt2262 {
+    fn default() -> Self {
+        Self {
+            unit: Duration::from_micros(350),
+        }
+    }
+}
+impl Pt2262 {
+    const PULSES: usize = 12 * 2 + 1;
+
+    pub fn sync(&self) -> Pulse {
+        Pulse {
+            high: self.unit,
+            low: self.unit * 31,
+        }
+    }
+}
+impl RemoteProtocol for Pt2262 {
+    type Code = Pt2262Code;
+
+    fn timing(&self) -> PwmTiming {
+        PwmTiming {
+            period: self.unit * 4,
+            high0: self.unit,
+            high1: self.unit * 3,
+        }
+    }
+    fn encode(&self, code: Pt2262Code) -> impl Iterator<Item = Pulse> {
+        let timing = self.timing();
+        code.0
+            .into_iter()
+            .flat_map(move |trit| trit.bits().map(|bit| timing.pulse(bit)))
+            .chain(core::iter::once(self.sync()))
+    }
+    fn decode(&self, pulses: &[Pulse]) -> Result<Pt2262Code, PulseError> {
+        if pulses.len() != Self::PULSES {
+            return Err(PulseError::Count {
+                expected: Self::PULSES,
+                found: pulses.len(),
+            });
+        }
+        let timing = self.timing();
+        if timing.classify(pulses[Self::PULSES - 1].high) != Some(false) {
+            return Err(PulseError::Width(Self::PULSES - 1));
+        }
+        let mut code = [Trit::Zero; 12];
+        for (idx, trit) in code.iter_mut().enumerate() {
+            let bit = |offset: usize| {
+                timing
+                    .classify(pulses[idx * 2 + offset].high)
+                    .ok_or(PulseError::Width(idx * 2 + offset))
+            };
+            *trit = Trit::from_bits([bit(0)?, bit(1)?]).ok_or(PulseError::Width(idx * 2))?;
+        }
+        Ok(Pt2262Code(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let pt2262 = Pt2262::default();
+        let code = Pt2262Code([
+            Trit::Float,
+            Trit::Zero,
+            Trit::One,
+            Trit::Float,
+            Trit::Zero,
+            Trit::Zero,
+            Trit::Float,
+            Trit::One,
+            Trit::Zero,
+            Trit::One,
+            Trit::Zero,
+            Trit::Zero,
+        ]);
+        let pulses: Vec<_> = pt2262.encode(code).collect();
+        assert_eq!(pulses.len(), 25);
+        assert_eq!(pulses[24], pt2262.sync());
+        assert_eq!(pt2262.decode(&pulses), Ok(code));
+
+        let mut invalid = pulses;
+        invalid.swap(0, 1);
+        assert_eq!(pt2262.decode(&invalid), Err(PulseError::Width(0)));
+    }
+}
@@ -0,0 +1,288 @@
+//! Pulse-width coded remotes, each bit is a high pulse followed by low
+
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::io;
+
+/// PWM bit timing, each bit is high for `high0` or `high1` from the start of `period`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PwmTiming {
+    pub period: Duration,
+    pub high0: Duration,
+    pub high1: Duration,
+}
+impl PwmTiming {
+    pub fn pulse(&self, bit: bool) -> Pulse {
+        let high = if bit { self.high1 } else { self.high0 };
+        Pulse {
+            high,
+            low: self.period.saturating_sub(high),
+        }
+    }
+    /// Bit whose nominal high time is closer to `high`, `None` if it is off by more than
+    /// half of that
+    pub fn classify(&self, high: Duration) -> Option<bool> {
+        let err = |nominal: Duration| high.abs_diff(nominal);
+        let bit = err(self.high1) < err(self.high0);
+        let nominal = if bit { self.high1 } else { self.high0 };
+        (err(nominal) <= nominal / 2).then_some(bit)
+    }
+}
+
+/// High for `high`, then low for `low`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pulse {
+    pub high: Duration,
+    pub low: Duration,
+}
+
+/// Output level change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub time: Duration,
+    pub high: bool,
+}
+
+/// Edges of `pulses` sent back to back from `start`
+pub fn edges(
+    start: Duration,
+    pulses: impl IntoIterator<Item = Pulse>,
+) -> impl Iterator<Item = Edge> {
+    pulses
+        .into_iter()
+        .scan(start, |time, pulse| {
+            let rise = *time;
+            *time += pulse.high + pulse.low;
+            Some([
+                Edge {
+                    time: rise,
+                    high: true,
+                },
+                Edge {
+                    time: rise + pulse.high,
+                    high: false,
+                },
+            ])
+        })
+        .flatten()
+}
+
+/// Pulses between rising edges of `edges`, the last one ends at `end`. Leading low time and
+/// repeated levels are dropped. Edges out of order give zero length parts.
+pub fn pulses(edges: impl IntoIterator<Item = Edge>, end: Duration) -> impl Iterator<Item = Pulse> {
+    let mut edges = edges.into_iter();
+    let mut rise = None;
+    core::iter::from_fn(move || loop {
+        match (edges.next(), rise) {
+            (Some(Edge { time, high: true }), None) => rise = Some((time, None)),
+            (Some(Edge { time, high: false }), Some((start, None))) => {
+                rise = Some((start, Some(time)))
+            }
+            (Some(Edge { time, high: true }), Some((start, Some(fall)))) => {
+                rise = Some((time, None));
+                return Some(Pulse {
+                    high: fall.saturating_sub(start),
+                    low: time.saturating_sub(fall),
+                });
+            }
+            (Some(_), _) => {}
+            (None, Some((start, fall))) => {
+                rise = None;
+                let fall = fall.unwrap_or(end);
+                return Some(Pulse {
+                    high: fall.saturating_sub(start),
+                    low: end.saturating_sub(fall),
+                });
+            }
+            (None, None) => return None,
+        }
+    })
+}
+
+/// Sample the level every `sample_period` for `duration`, packed MSB first as `ykq368-decoder`
+/// and `gr-file-to-fst` read it. The signal is low before the first edge.
+#[cfg(feature = "std")]
+pub fn write_packed<W: io::Write>(
+    edges: impl IntoIterator<Item = Edge>,
+    sample_period: Duration,
+    duration: Duration,
+    writer: &mut W,
+) -> io::Result<()> {
+    if sample_period.is_zero() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "zero sample period",
+        ));
+    }
+    let samples = duration.as_nanos().div_ceil(sample_period.as_nanos());
+    let mut edges = edges.into_iter().peekable();
+    let mut high = false;
+    let mut buf = Vec::with_capacity(samples.div_ceil(8) as usize);
+    let (mut byte, mut filled) = (0u8, 0);
+    for idx in 0..samples {
+        let time = sample_period.as_nanos() * idx;
+        while let Some(edge) = edges.next_if(|e| e.time.as_nanos() <= time) {
+            high = edge.high;
+        }
+        byte = byte << 1 | high as u8;
+        filled += 1;
+        if filled == 8 {
+            buf.push(byte);
+            (byte, filled) = (0, 0);
+        }
+    }
+    if filled != 0 {
+        buf.push(byte << (8 - filled));
+    }
+    writer.write_all(&buf)
+}
+
+/// Edges of samples taken every `sample_period` and packed MSB first, as [`write_packed`]
+/// writes them. The signal is low before the first sample.
+pub fn read_packed(
+    bytes: impl IntoIterator<Item = u8>,
+    sample_period: Duration,
+) -> impl Iterator<Item = Edge> {
+    bytes
+        .into_iter()
+        .flat_map(|byte| (0..8).rev().map(move |bit| byte >> bit & 1 != 0))
+        .enumerate()
+        .scan(false, move |level, (idx, high)| {
+            let edge = (high != *level).then(|| Edge {
+                time: sample_period * idx as u32,
+                high,
+            });
+            *level = high;
+            Some(edge)
+        })
+        .flatten()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PulseError {
+    Count {
+        expected: usize,
+        found: usize,
+    },
+    /// Index of a pulse matching no symbol
+    Width(usize),
+}
+impl core::fmt::Display for PulseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Count { expected, found } => {
+                write!(f, "expected {expected} pulses, found {found}")
+            }
+            Self::Width(idx) => write!(f, "pulse {idx} has invalid width"),
+        }
+    }
+}
+impl core::error::Error for PulseError {}
+
+/// Frame coding of a remote family
+pub trait RemoteProtocol {
+    type Code;
+
+    /// Nominal bit timing
+    fn timing(&self) -> PwmTiming;
+    /// Pulses of one frame, including the sync or gap before the next one
+    fn encode(&self, code: Self::Code) -> impl Iterator<Item = Pulse>;
+    /// Inverse of [`RemoteProtocol::encode`], the low time of the last pulse is not checked
+    fn decode(&self, pulses: &[Pulse]) -> Result<Self::Code, PulseError>;
+    /// Split received pulses into parts holding at most one frame each, for
+    /// [`RemoteProtocol::decode`]. By default frames end with a low longer than two bit periods.
+    fn frames<'p>(&self, pulses: &'p [Pulse]) -> impl Iterator<Item = &'p [Pulse]> {
+        let gap = self.timing().period * 2;
+        pulses.split_inclusive(move |pulse| pulse.low > gap)
+    }
+}
+
+/// Read `N` bits MSB first from `pulses` starting at `offset`
+pub(crate) fn decode_bits<const N: usize>(
+    timing: &PwmTiming,
+    pulses: &[Pulse],
+    offset: usize,
+) -> Result<u64, PulseError> {
+    pulses[offset..offset + N]
+        .iter()
+        .enumerate()
+        .try_fold(0, |acc, (idx, pulse)| match timing.classify(pulse.high) {
+            Some(bit) => Ok(acc << 1 | bit as u64),
+            None => Err(PulseError::Width(offset + idx)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_pulse_roundtrip() {
+        let timing = PwmTiming {
+            period: Duration::from_micros(1000),
+            high0: Duration::from_micros(700),
+            high1: Duration::from_micros(200),
+        };
+        let sent: Vec<_> = [true, false, true].map(|b| timing.pulse(b)).into();
+        let edges: Vec<_> = edges(Duration::from_millis(5), sent.iter().copied()).collect();
+        assert_eq!(edges.len(), 6);
+        assert_eq!(
+            pulses(edges.iter().copied(), Duration::from_millis(8)).collect::<Vec<_>>(),
+            sent
+        );
+        assert_eq!(timing.classify(Duration::from_micros(260)), Some(true));
+        assert_eq!(timing.classify(Duration::from_micros(500)), Some(false));
+        assert_eq!(timing.classify(Duration::from_micros(1100)), None);
+    }
+
+    #[test]
+    fn pulses_out_of_order() {
+        let edge = |us, high| Edge {
+            time: Duration::from_micros(us),
+            high,
+        };
+        let edges = [
+            edge(500, true),
+            edge(300, false),
+            edge(200, true),
+            edge(400, false),
+        ];
+        assert_eq!(
+            pulses(edges, Duration::from_micros(100)).collect::<Vec<_>>(),
+            [
+                Pulse {
+                    high: Duration::ZERO,
+                    low: Duration::ZERO
+                },
+                Pulse {
+                    high: Duration::from_micros(200),
+                    low: Duration::ZERO
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn packed_roundtrip() {
+        let timing = PwmTiming {
+            period: Duration::from_micros(1000),
+            high0: Duration::from_micros(700),
+            high1: Duration::from_micros(200),
+        };
+        let sent = [true, false, true].map(|b| timing.pulse(b));
+        let edges: Vec<_> = edges(Duration::from_millis(5), sent.iter().copied()).collect();
+        let mut packed = Vec::new();
+        write_packed(
+            edges.iter().copied(),
+            Duration::from_micros(100),
+            Duration::from_millis(8),
+            &mut packed,
+        )
+        .unwrap();
+        assert_eq!(
+            read_packed(packed, Duration::from_micros(100)).collect::<Vec<_>>(),
+            edges
+        );
+    }
+}
@@ -0,0 +1,466 @@
+//! Recording, decoding and replaying bytes exchanged with the FPGA over the serial link
+
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{self, Read, Write},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use strum::VariantArray;
+
+use crate::{
+    remote_control::atsmart::ykq368::{Command, SendInstr},
+    transceiver::cc1101::{CommandStrobe, ConfigRegAddr, ReadWrite, StatusRegAddr},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Host to FPGA
+    Tx,
+    /// FPGA to host
+    Rx,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub timestamp: SystemTime,
+    pub direction: Direction,
+    #[serde(with = "bytes")]
+    pub data: Vec<u8>,
+}
+
+/// Serialize `Vec<u8>` as byte string instead of array
+mod bytes {
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(data)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Vec<u8>;
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("byte string")
+            }
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(v.to_vec())
+            }
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(v)
+            }
+        }
+        deserializer.deserialize_byte_buf(Visitor)
+    }
+}
+
+/// Write a single entry of a CBOR sequence
+pub fn write_entry(writer: &mut impl Write, entry: &TraceEntry) -> io::Result<()> {
+    ciborium::into_writer(entry, writer).map_err(|e| match e {
+        ciborium::ser::Error::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+    })
+}
+/// Read all entries of a trace file
+pub fn read_entries(reader: impl Read) -> io::Result<Vec<TraceEntry>> {
+    let mut reader = io::BufReader::new(reader);
+    let mut ret = Vec::new();
+    while !io::BufRead::fill_buf(&mut reader)?.is_empty() {
+        ret.push(
+            ciborium::from_reader(&mut reader)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?,
+        );
+    }
+    Ok(ret)
+}
+
+/// Port wrapper writing every read and write to `log` as CBOR sequence of [`TraceEntry`]
+pub struct Tracer<P, L> {
+    inner: P,
+    log: L,
+}
+impl<P, L: Write> Tracer<P, L> {
+    pub fn new(inner: P, log: L) -> Self {
+        Self { inner, log }
+    }
+    pub fn into_inner(self) -> (P, L) {
+        (self.inner, self.log)
+    }
+
+    fn record(&mut self, direction: Direction, data: &[u8]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        write_entry(
+            &mut self.log,
+            &TraceEntry {
+                timestamp: SystemTime::now(),
+                direction,
+                data: data.to_vec(),
+            },
+        )?;
+        self.log.flush()
+    }
+}
+impl<P: Read, L: Write> Read for Tracer<P, L> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.record(Direction::Rx, &buf[..len])?;
+        Ok(len)
+    }
+}
+impl<P: Write, L: Write> Write for Tracer<P, L> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.record(Direction::Tx, &buf[..len])?;
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Fake port returning recorded responses
+pub struct ReplayPort {
+    tx: VecDeque<u8>,
+    rx: VecDeque<u8>,
+    /// Fail if written bytes differ from the recorded ones
+    strict: bool,
+}
+impl ReplayPort {
+    pub fn new(entries: &[TraceEntry], strict: bool) -> Self {
+        let (tx, rx) = split_directions(entries);
+        Self {
+            tx: tx.into(),
+            rx: rx.into(),
+            strict,
+        }
+    }
+}
+impl Read for ReplayPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.rx.len());
+        for (dst, src) in buf.iter_mut().zip(self.rx.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+}
+impl Write for ReplayPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (idx, v) in buf.iter().enumerate() {
+            match self.tx.pop_front() {
+                Some(expected) if !self.strict || expected == *v => (),
+                expected => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("byte {idx} written {v:#04x}, recorded {expected:02x?}"),
+                    ))
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn split_directions(entries: &[TraceEntry]) -> (Vec<u8>, Vec<u8>) {
+    let mut tx = Vec::new();
+    let mut rx = Vec::new();
+    for entry in entries {
+        match entry.direction {
+            Direction::Tx => tx.extend(&entry.data),
+            Direction::Rx => rx.extend(&entry.data),
+        }
+    }
+    (tx, rx)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transaction {
+    SelectEndpoint(u8),
+    ExitEndpoint(u8),
+    /// CC1101 endpoint GDO output enable, bit n for `GDOn`
+    GdoDirection(u8),
+    Cc1101 {
+        rw: ReadWrite,
+        burst: bool,
+        addr: u8,
+        /// Written bytes for write, read bytes for read
+        data: Vec<u8>,
+        status: Option<u8>,
+    },
+    Ykq368(Command),
+    /// Bytes not understood, including truncated transfers
+    Unknown {
+        endpoint: u8,
+        data: Vec<u8>,
+    },
+}
+
+fn addr_name(addr: u8, rw: ReadWrite, burst: bool) -> String {
+    match addr {
+        0x00..=0x2F => match ConfigRegAddr::ALL.get(addr as usize) {
+            Some(reg) => format!("{reg:?}"),
+            None => format!("{addr:#04x}"),
+        },
+        0x30..=0x3D if rw == ReadWrite::Read && burst => StatusRegAddr::VARIANTS
+            .iter()
+            .find(|r| **r as u8 == addr)
+            .map_or_else(|| format!("{addr:#04x}"), |r| format!("{r:?}")),
+        0x30..=0x3D => CommandStrobe::VARIANTS
+            .iter()
+            .find(|r| **r as u8 == addr)
+            .map_or_else(|| format!("{addr:#04x}"), |r| format!("{r:?}")),
+        0x3E => "PATABLE".to_owned(),
+        _ => "FIFO".to_owned(),
+    }
+}
+
+fn fmt_hex(f: &mut std::fmt::Formatter<'_>, data: &[u8]) -> std::fmt::Result {
+    for (idx, v) in data.iter().enumerate() {
+        if idx != 0 {
+            f.write_str(" ")?;
+        }
+        write!(f, "{v:02x}")?;
+    }
+    Ok(())
+}
+
+impl Display for Transaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SelectEndpoint(ep) => write!(f, "endpoint select {ep}"),
+            Self::ExitEndpoint(ep) => write!(f, "endpoint exit {ep}"),
+            Self::GdoDirection(dir) => write!(f, "gdo output enable {dir:03b}"),
+            Self::Cc1101 {
+                rw,
+                burst,
+                addr,
+                data,
+                status,
+            } => {
+                let is_strobe = (0x30..=0x3D).contains(addr) && !(*rw == ReadWrite::Read && *burst);
+                if is_strobe {
+                    write!(f, "strobe {}", addr_name(*addr, *rw, *burst))?;
+                } else {
+                    write!(
+                        f,
+                        "{}{} {}",
+                        if *burst && *addr < 0x30 { "burst " } else { "" },
+                        match rw {
+                            ReadWrite::Read => "read",
+                            ReadWrite::Write => "write",
+                        },
+                        addr_name(*addr, *rw, *burst)
+                    )?;
+                    if *burst && data.len() > 1 && *addr < 0x2F {
+                        write!(
+                            f,
+                            "..{}",
+                            addr_name((*addr + data.len() as u8 - 1).min(0x2E), *rw, *burst)
+                        )?;
+                    }
+                    f.write_str(" = ")?;
+                    fmt_hex(f, data)?;
+                }
+                match status {
+                    Some(s) => write!(f, ", status {s:#04x}"),
+                    None => Ok(()),
+                }
+            }
+            Self::Ykq368(cmd) => match SendInstr::try_from(*cmd) {
+                Ok(instr) => write!(f, "YKQ368 {instr:?}"),
+                Err(e) => write!(f, "YKQ368 {cmd:?} ({e})"),
+            },
+            Self::Unknown { endpoint, data } => {
+                write!(f, "unknown on endpoint {endpoint}: ")?;
+                fmt_hex(f, data)
+            }
+        }
+    }
+}
+
+/// Decode recorded traffic into transactions, following endpoint selection
+pub fn decode(entries: &[TraceEntry]) -> Vec<Transaction> {
+    let (tx, rx) = split_directions(entries);
+    let mut rx = rx.into_iter();
+    let mut take_rx = |n: usize| rx.by_ref().take(n).collect::<Vec<u8>>();
+
+    let mut ret = Vec::new();
+    let mut endpoint = 0;
+    let mut idx = 0;
+    while idx < tx.len() {
+        let rest = &tx[idx..];
+        match endpoint {
+            0 => {
+                if rest[0] != 0 {
+                    endpoint = rest[0] & 0x3;
+                    ret.push(Transaction::SelectEndpoint(endpoint));
+                }
+                idx += 1;
+            }
+            1 => {
+                let header = rest[0];
+                if header & 0x80 == 0 {
+                    match header >> 4 & 0x7 {
+                        0 => {
+                            ret.push(Transaction::ExitEndpoint(endpoint));
+                            endpoint = 0;
+                        }
+                        1 => ret.push(Transaction::GdoDirection(header & 0x7)),
+                        _ => ret.push(Transaction::Unknown {
+                            endpoint,
+                            data: vec![header],
+                        }),
+                    }
+                    idx += 1;
+                    continue;
+                }
+                let Some(&addr_byte) = rest.get(1) else {
+                    ret.push(Transaction::Unknown {
+                        endpoint,
+                        data: rest.to_vec(),
+                    });
+                    break;
+                };
+                let rw = if addr_byte & 0x80 != 0 {
+                    ReadWrite::Read
+                } else {
+                    ReadWrite::Write
+                };
+                let burst = addr_byte & 0x40 != 0;
+                let addr = addr_byte & 0x3f;
+                let (write_len, read_len) = if (0x30..=0x3D).contains(&addr) {
+                    if rw == ReadWrite::Read && burst {
+                        (0, 2)
+                    } else {
+                        (0, 1)
+                    }
+                } else if burst {
+                    let count = (header & 0x7f) as usize;
+                    match rw {
+                        ReadWrite::Read => (0, 1 + count),
+                        ReadWrite::Write => (count, 1 + count),
+                    }
+                } else {
+                    match rw {
+                        ReadWrite::Read => (0, 2),
+                        ReadWrite::Write => (1, 2),
+                    }
+                };
+                let Some(written) = rest.get(2..2 + write_len) else {
+                    ret.push(Transaction::Unknown {
+                        endpoint,
+                        data: rest.to_vec(),
+                    });
+                    break;
+                };
+                let response = take_rx(read_len);
+                let status = response.first().copied();
+                ret.push(Transaction::Cc1101 {
+                    rw,
+                    burst,
+                    addr,
+                    data: match rw {
+                        ReadWrite::Read => response.get(1..).unwrap_or_default().to_vec(),
+                        ReadWrite::Write => written.to_vec(),
+                    },
+                    status,
+                });
+                idx += 2 + write_len;
+            }
+            3 => {
+                let Some(bytes) = rest.first_chunk::<8>() else {
+                    ret.push(Transaction::Unknown {
+                        endpoint,
+                        data: rest.to_vec(),
+                    });
+                    break;
+                };
+                if bytes[0] & 0x60 == 0 {
+                    ret.push(Transaction::ExitEndpoint(endpoint));
+                    endpoint = 0;
+                } else {
+                    ret.push(Transaction::Ykq368(Command::from(*bytes)));
+                }
+                idx += 8;
+            }
+            _ => {
+                // endpoint 2 discards everything and never exits
+                ret.push(Transaction::Unknown {
+                    endpoint,
+                    data: rest.to_vec(),
+                });
+                break;
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transceiver::cc1101::Cc1101;
+
+    #[test]
+    fn record_decode_replay() {
+        /// Answers every transfer with status 0x0f
+        struct Port;
+        impl Read for Port {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                buf.fill(0x0f);
+                Ok(buf.len())
+            }
+        }
+        impl Write for Port {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut log = Vec::new();
+        {
+            let mut tracer = Tracer::new(Port, &mut log);
+            tracer.write_all(&[0x01]).unwrap();
+            let mut cc1101 = Cc1101::new(&mut tracer);
+            cc1101
+                .write_raw_config_burst(ConfigRegAddr::FREQ2, &[0x10, 0xb0, 0x71])
+                .unwrap();
+            cc1101.exit().unwrap();
+        }
+
+        let entries = read_entries(log.as_slice()).unwrap();
+        let decoded = decode(&entries)
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            [
+                "endpoint select 1",
+                "burst write FREQ2..FREQ0 = 10 b0 71, status 0x0f",
+                "endpoint exit 1",
+            ]
+        );
+
+        let mut replay = ReplayPort::new(&entries, true);
+        replay.write_all(&[0x01]).unwrap();
+        let mut cc1101 = Cc1101::new(&mut replay);
+        cc1101
+            .write_raw_config_burst(ConfigRegAddr::FREQ2, &[0x10, 0xb0, 0x71])
+            .unwrap();
+        assert!(cc1101
+            .write_raw_config_reg(true, ConfigRegAddr::FREQ2, 0x10)
+            .is_err());
+    }
+}
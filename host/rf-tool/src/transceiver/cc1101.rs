@@ -1,8 +1,32 @@
-use std::{
-    fmt::Debug,
-    io::{self, Read, Write},
-    num::NonZeroU8,
-};
+use core::fmt::Debug;
+
+#[cfg(feature = "tokio")]
+pub mod asynch;
+#[cfg(feature = "std")]
+pub mod cca;
+#[cfg(feature = "std")]
+pub mod diag;
+#[cfg(feature = "std")]
+mod driver;
+#[cfg(feature = "std")]
+pub mod event;
+#[cfg(all(test, feature = "std"))]
+mod fake;
+#[cfg(feature = "std")]
+pub mod fields;
+#[cfg(feature = "std")]
+pub mod freq;
+#[cfg(feature = "std")]
+pub mod gdo;
+#[cfg(feature = "std")]
+pub mod region;
+#[cfg(feature = "std")]
+pub mod rx;
+#[cfg(feature = "std")]
+pub mod wor;
+
+#[cfg(feature = "std")]
+pub use driver::{Cc1101, PowerDownMode, VerifyError};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReadWrite {
@@ -11,7 +35,8 @@ pub enum ReadWrite {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumString)]
+#[strum(ascii_case_insensitive)]
 pub enum ConfigRegAddr {
     /// `GDO2` output pin configuration
     IOCFG2 = 0x00,
@@ -109,8 +134,152 @@ pub enum ConfigRegAddr {
     TEST0 = 0x2E,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl ConfigRegAddr {
+    /// All config registers, indexed by address
+    pub const ALL: [Self; 0x2F] = [
+        Self::IOCFG2,
+        Self::IOCFG1,
+        Self::IOCFG0,
+        Self::FIFOTHR,
+        Self::SYNC1,
+        Self::SYNC0,
+        Self::PKTLEN,
+        Self::PKTCTRL1,
+        Self::PKTCTRL0,
+        Self::ADDR,
+        Self::CHANNR,
+        Self::FSCTRL1,
+        Self::FSCTRL0,
+        Self::FREQ2,
+        Self::FREQ1,
+        Self::FREQ0,
+        Self::MDMCFG4,
+        Self::MDMCFG3,
+        Self::MDMCFG2,
+        Self::MDMCFG1,
+        Self::MDMCFG0,
+        Self::DEVIATN,
+        Self::MCSM2,
+        Self::MCSM1,
+        Self::MCSM0,
+        Self::FOCCFG,
+        Self::BSCFG,
+        Self::AGCTRL2,
+        Self::AGCTRL1,
+        Self::AGCTRL0,
+        Self::WOREVT1,
+        Self::WOREVT0,
+        Self::WORCTRL,
+        Self::FREND1,
+        Self::FREND0,
+        Self::FSCAL3,
+        Self::FSCAL2,
+        Self::FSCAL1,
+        Self::FSCAL0,
+        Self::RCCTRL1,
+        Self::RCCTRL0,
+        Self::FSTEST,
+        Self::PTEST,
+        Self::AGCTEST,
+        Self::TEST2,
+        Self::TEST1,
+        Self::TEST0,
+    ];
+
+    /// Value after reset, from the datasheet register overview
+    pub const fn reset_value(self) -> u8 {
+        match self {
+            Self::IOCFG2 => 0x29,
+            Self::IOCFG1 => 0x2E,
+            Self::IOCFG0 => 0x3F,
+            Self::FIFOTHR => 0x07,
+            Self::SYNC1 => 0xD3,
+            Self::SYNC0 => 0x91,
+            Self::PKTLEN => 0xFF,
+            Self::PKTCTRL1 => 0x04,
+            Self::PKTCTRL0 => 0x45,
+            Self::ADDR => 0x00,
+            Self::CHANNR => 0x00,
+            Self::FSCTRL1 => 0x0F,
+            Self::FSCTRL0 => 0x00,
+            Self::FREQ2 => 0x1E,
+            Self::FREQ1 => 0xC4,
+            Self::FREQ0 => 0xEC,
+            Self::MDMCFG4 => 0x8C,
+            Self::MDMCFG3 => 0x22,
+            Self::MDMCFG2 => 0x02,
+            Self::MDMCFG1 => 0x22,
+            Self::MDMCFG0 => 0xF8,
+            Self::DEVIATN => 0x47,
+            Self::MCSM2 => 0x07,
+            Self::MCSM1 => 0x30,
+            Self::MCSM0 => 0x04,
+            Self::FOCCFG => 0x36,
+            Self::BSCFG => 0x6C,
+            Self::AGCTRL2 => 0x03,
+            Self::AGCTRL1 => 0x40,
+            Self::AGCTRL0 => 0x91,
+            Self::WOREVT1 => 0x87,
+            Self::WOREVT0 => 0x6B,
+            Self::WORCTRL => 0xF8,
+            Self::FREND1 => 0x56,
+            Self::FREND0 => 0x10,
+            Self::FSCAL3 => 0xA9,
+            Self::FSCAL2 => 0x0A,
+            Self::FSCAL1 => 0x20,
+            Self::FSCAL0 => 0x0D,
+            Self::RCCTRL1 => 0x41,
+            Self::RCCTRL0 => 0x00,
+            Self::FSTEST => 0x59,
+            Self::PTEST => 0x7F,
+            Self::AGCTEST => 0x3F,
+            Self::TEST2 => 0x88,
+            Self::TEST1 => 0x31,
+            Self::TEST0 => 0x0B,
+        }
+    }
+    /// Bits which are used, unused bits are ignored on write and read back as 0
+    pub const fn writable_mask(self) -> u8 {
+        match self {
+            Self::IOCFG2 => 0x7F,
+            Self::FIFOTHR => 0x7F,
+            Self::PKTCTRL1 => 0xEF,
+            Self::PKTCTRL0 => 0x77,
+            Self::FSCTRL1 => 0x1F,
+            Self::FREQ2 => 0x3F,
+            Self::MDMCFG1 => 0xF3,
+            Self::DEVIATN => 0x77,
+            Self::MCSM2 => 0x1F,
+            Self::MCSM1 => 0x3F,
+            Self::MCSM0 => 0x3F,
+            Self::FOCCFG => 0x3F,
+            Self::AGCTRL1 => 0x7F,
+            Self::WORCTRL => 0xFB,
+            Self::FREND0 => 0x37,
+            Self::FSCAL2 => 0x3F,
+            Self::FSCAL1 => 0x3F,
+            Self::FSCAL0 => 0x7F,
+            Self::RCCTRL1 => 0x7F,
+            Self::RCCTRL0 => 0x7F,
+            _ => 0xFF,
+        }
+    }
+    /// `FSCAL3..FSCAL0`, updated by the chip on calibration
+    pub const fn is_calibration(self) -> bool {
+        matches!(
+            self,
+            Self::FSCAL3 | Self::FSCAL2 | Self::FSCAL1 | Self::FSCAL0
+        )
+    }
+    /// `FSTEST`, `PTEST` and `AGCTEST`, which should never be written
+    pub const fn is_test_only(self) -> bool {
+        matches!(self, Self::FSTEST | Self::PTEST | Self::AGCTEST)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::VariantArray)]
 #[repr(u8)]
+#[strum(ascii_case_insensitive)]
 pub enum CommandStrobe {
     /// Reset chip
     SRES = 0x30,
@@ -146,7 +315,8 @@ pub enum CommandStrobe {
 
 #[allow(non_camel_case_types)]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::VariantArray)]
+#[strum(ascii_case_insensitive)]
 pub enum StatusRegAddr {
     /// Part number for CC1101
     PARTNUM = 0x30,
@@ -263,21 +433,170 @@ pub enum GdoCfg {
     ClkXosc192 = 0x3F,
 }
 
-#[inline]
-const fn to_nonzero_u8<const N: usize>() -> NonZeroU8 {
-    if N > 128 {
-        panic!()
-    } else {
-        match NonZeroU8::new(N as u8) {
-            Some(v) => v,
-            None => panic!(),
-        }
+impl TryFrom<u8> for GdoCfg {
+    type Error = u8;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        const CFGS: [GdoCfg; 45] = [
+            GdoCfg::RxFifoThreshold,
+            GdoCfg::RxFifoThresholdOrEndOfPacket,
+            GdoCfg::TxFifoThreshold,
+            GdoCfg::TxFifoFull,
+            GdoCfg::RxFifoOverflow,
+            GdoCfg::TxFifoUnderflow,
+            GdoCfg::SyncWord,
+            GdoCfg::PacketCrcOkReceived,
+            GdoCfg::PreambleQualityReached,
+            GdoCfg::ClearChannelAssessment,
+            GdoCfg::LockDetectorOutput,
+            GdoCfg::SerialClock,
+            GdoCfg::SerialSynchronousDataOutput,
+            GdoCfg::SerialDataOutput,
+            GdoCfg::CarrierSense,
+            GdoCfg::CrcOk,
+            GdoCfg::RxHardData1,
+            GdoCfg::RxHardData0,
+            GdoCfg::PaPd,
+            GdoCfg::LnaPd,
+            GdoCfg::RxSymbolTick,
+            GdoCfg::WorEvnt0,
+            GdoCfg::WorEvnt1,
+            GdoCfg::Clk256,
+            GdoCfg::Clk32k,
+            GdoCfg::ChipRdy,
+            GdoCfg::XoscStable,
+            GdoCfg::HighImpedance,
+            GdoCfg::Zero,
+            GdoCfg::ClkXosc1,
+            GdoCfg::ClkXosc1_5,
+            GdoCfg::ClkXosc2,
+            GdoCfg::ClkXosc3,
+            GdoCfg::ClkXosc4,
+            GdoCfg::ClkXosc6,
+            GdoCfg::ClkXosc8,
+            GdoCfg::ClkXosc12,
+            GdoCfg::ClkXosc16,
+            GdoCfg::ClkXosc24,
+            GdoCfg::ClkXosc32,
+            GdoCfg::ClkXosc48,
+            GdoCfg::ClkXosc64,
+            GdoCfg::ClkXosc96,
+            GdoCfg::ClkXosc128,
+            GdoCfg::ClkXosc192,
+        ];
+        CFGS.into_iter().find(|c| *c as u8 == value).ok_or(value)
+    }
+}
+
+/// Main radio control state machine state, value of `MARCSTATE`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarcState {
+    Sleep = 0x00,
+    Idle = 0x01,
+    Xoff = 0x02,
+    VcoonMc = 0x03,
+    RegonMc = 0x04,
+    Mancal = 0x05,
+    Vcoon = 0x06,
+    Regon = 0x07,
+    Startcal = 0x08,
+    Bwboost = 0x09,
+    FsLock = 0x0A,
+    Ifadcon = 0x0B,
+    Endcal = 0x0C,
+    Rx = 0x0D,
+    RxEnd = 0x0E,
+    RxRst = 0x0F,
+    TxrxSwitch = 0x10,
+    RxfifoOverflow = 0x11,
+    Fstxon = 0x12,
+    Tx = 0x13,
+    TxEnd = 0x14,
+    RxtxSwitch = 0x15,
+    TxfifoUnderflow = 0x16,
+}
+impl MarcState {
+    pub const fn is_rx(self) -> bool {
+        matches!(self, Self::Rx | Self::RxEnd | Self::RxRst)
+    }
+}
+impl TryFrom<u8> for MarcState {
+    type Error = u8;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        const STATES: [MarcState; 0x17] = [
+            MarcState::Sleep,
+            MarcState::Idle,
+            MarcState::Xoff,
+            MarcState::VcoonMc,
+            MarcState::RegonMc,
+            MarcState::Mancal,
+            MarcState::Vcoon,
+            MarcState::Regon,
+            MarcState::Startcal,
+            MarcState::Bwboost,
+            MarcState::FsLock,
+            MarcState::Ifadcon,
+            MarcState::Endcal,
+            MarcState::Rx,
+            MarcState::RxEnd,
+            MarcState::RxRst,
+            MarcState::TxrxSwitch,
+            MarcState::RxfifoOverflow,
+            MarcState::Fstxon,
+            MarcState::Tx,
+            MarcState::TxEnd,
+            MarcState::RxtxSwitch,
+            MarcState::TxfifoUnderflow,
+        ];
+        STATES.get(value as usize).copied().ok_or(value)
     }
 }
 
 pub struct Status(pub u8);
+/// `STATE` field of the status byte
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChipState {
+    Idle = 0,
+    Rx = 1,
+    Tx = 2,
+    /// Fast TX ready
+    Fstxon = 3,
+    /// Frequency synthesizer calibration is running
+    Calibrate = 4,
+    /// PLL is settling
+    Settling = 5,
+    /// RX FIFO has overflowed. Read out any useful data, then flush the FIFO with `SFRX`
+    RxfifoOverflow = 6,
+    /// TX FIFO has underflowed. Acknowledge with `SFTX`
+    TxfifoUnderflow = 7,
+}
+
+impl Status {
+    /// `CHIP_RDYn` is low, i.e. the crystal is running and the chip accepts commands
+    pub const fn chip_ready(&self) -> bool {
+        self.0 & 0x80 == 0
+    }
+    pub const fn state(&self) -> ChipState {
+        match (self.0 >> 4) & 0x7 {
+            0 => ChipState::Idle,
+            1 => ChipState::Rx,
+            2 => ChipState::Tx,
+            3 => ChipState::Fstxon,
+            4 => ChipState::Calibrate,
+            5 => ChipState::Settling,
+            6 => ChipState::RxfifoOverflow,
+            _ => ChipState::TxfifoUnderflow,
+        }
+    }
+    /// Bytes available in RX FIFO for read access, free bytes in TX FIFO for write access,
+    /// saturated at 15
+    pub const fn fifo_bytes_available(&self) -> u8 {
+        self.0 & 0x0f
+    }
+}
 impl Debug for Status {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:02x}", self.0)
     }
 }
@@ -325,141 +644,3 @@ impl TransferCmd {
         Self::burst(rw, count, 0x3f)
     }
 }
-
-#[inline]
-fn arr_to_status<const N: usize>(arr: [u8; N]) -> [Status; N] {
-    std::array::from_fn(|idx| Status(arr[idx]))
-}
-
-pub struct Cc1101<'a, P>(pub &'a mut P);
-impl<'a, P: Read + Write> Cc1101<'a, P> {
-    pub fn new(port: &'a mut P) -> Self {
-        Self(port)
-    }
-
-    fn read_raw(&mut self, cmd: TransferCmd) -> io::Result<(Status, u8)> {
-        self.0.write_all(&cmd.0)?;
-        let mut buf = [0, 0];
-        self.0.read_exact(&mut buf)?;
-        Ok((Status(buf[0]), buf[1]))
-    }
-    fn read_raw_burst<const N: usize>(
-        &mut self,
-        cmd: TransferCmd,
-    ) -> io::Result<(Status, [u8; N])> {
-        self.0.write_all(&cmd.0)?;
-        let mut status = [0; 1];
-        let mut ret = [0; N];
-        self.0.read_exact(&mut status)?;
-        self.0.read_exact(&mut ret)?;
-        Ok((Status(status[0]), ret))
-    }
-    fn write_raw(&mut self, cmd: TransferCmd, data: u8) -> io::Result<(Status, Status)> {
-        self.0.write_all(&[cmd.0[0], cmd.0[1], data])?;
-        let mut buf = [0, 0];
-        self.0.read_exact(&mut buf)?;
-        Ok((Status(buf[0]), Status(buf[1])))
-    }
-    fn write_raw_burst<const N: usize>(
-        &mut self,
-        cmd: TransferCmd,
-        data: &[u8; N],
-    ) -> io::Result<(Status, [Status; N])> {
-        self.0.write_all(&cmd.0)?;
-        self.0.write_all(data)?;
-        let mut status = [0; 1];
-        let mut ret = [0; N];
-        self.0.read_exact(&mut status)?;
-        self.0.read_exact(&mut ret)?;
-        Ok((Status(status[0]), arr_to_status(ret)))
-    }
-
-    pub fn read_raw_status_reg(
-        &mut self,
-        close: bool,
-        addr: StatusRegAddr,
-    ) -> io::Result<(Status, u8)> {
-        self.read_raw(TransferCmd::status_reg(close, addr))
-    }
-    pub fn read_raw_config_reg(
-        &mut self,
-        close: bool,
-        addr: ConfigRegAddr,
-    ) -> io::Result<(Status, u8)> {
-        self.read_raw(TransferCmd::config_reg(close, ReadWrite::Read, addr))
-    }
-    pub fn read_raw_config_burst<const N: usize>(
-        &mut self,
-        start_addr: ConfigRegAddr,
-    ) -> io::Result<(Status, [u8; N])> {
-        self.read_raw_burst(TransferCmd::config_reg_burst(
-            ReadWrite::Read,
-            start_addr,
-            to_nonzero_u8::<N>().get(),
-        ))
-    }
-    pub fn write_raw_config_burst<const N: usize>(
-        &mut self,
-        start_addr: ConfigRegAddr,
-        data: &[u8; N],
-    ) -> io::Result<(Status, [Status; N])> {
-        self.write_raw_burst(
-            TransferCmd::config_reg_burst(ReadWrite::Write, start_addr, to_nonzero_u8::<N>().get()),
-            data,
-        )
-    }
-    pub fn write_raw_config_reg(
-        &mut self,
-        close: bool,
-        addr: ConfigRegAddr,
-        data: u8,
-    ) -> io::Result<(Status, Status)> {
-        self.write_raw(TransferCmd::config_reg(close, ReadWrite::Write, addr), data)
-    }
-
-    pub fn command_strobe(
-        &mut self,
-        close: bool,
-        rw: ReadWrite,
-        cmd: CommandStrobe,
-    ) -> io::Result<Status> {
-        self.0
-            .write_all(&TransferCmd::command_strobe(close, rw, cmd).0)?;
-        let mut buf = [0];
-        self.0.read_exact(&mut buf)?;
-        Ok(Status(buf[0]))
-    }
-
-    pub fn read_fifo(&mut self, close: bool) -> io::Result<(Status, u8)> {
-        self.read_raw(TransferCmd::fifo(close, ReadWrite::Read))
-    }
-    pub fn read_fifo_burst<const N: usize>(&mut self) -> io::Result<(Status, [u8; N])> {
-        self.read_raw_burst(TransferCmd::fifo_burst(
-            ReadWrite::Read,
-            to_nonzero_u8::<N>().get(),
-        ))
-    }
-    pub fn write_fifo(&mut self, close: bool, data: u8) -> io::Result<(Status, Status)> {
-        self.write_raw(TransferCmd::fifo(close, ReadWrite::Write), data)
-    }
-    pub fn write_fifo_burst<const N: usize>(
-        &mut self,
-        data: &[u8; N],
-    ) -> io::Result<(Status, [Status; N])> {
-        self.write_raw_burst(
-            TransferCmd::fifo_burst(ReadWrite::Write, to_nonzero_u8::<N>().get()),
-            data,
-        )
-    }
-
-    pub fn read_pa_table(&mut self) -> io::Result<(Status, [u8; 8])> {
-        self.read_raw_burst(TransferCmd::pa_table(ReadWrite::Read, 8))
-    }
-    pub fn write_pa_table(&mut self, data: &[u8; 8]) -> io::Result<(Status, [Status; 8])> {
-        self.write_raw_burst(TransferCmd::pa_table(ReadWrite::Write, 8), data)
-    }
-
-    pub fn exit(self) -> io::Result<()> {
-        self.0.write_all(&[0x00])
-    }
-}
@@ -0,0 +1,388 @@
+//! Async driver over tokio I/O, sharing [`TransferCmd`] encoding, the shadow copy and
+//! regulation checks with [`Cc1101`]. Register, FIFO, PATABLE and power down access only,
+//! the higher level helpers of the other modules are blocking.
+//!
+//! [`Cc1101`]: super::Cc1101
+
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{
+    driver::{arr_to_status, check_verify, to_nonzero_u8, Missing, Shadow, CHIP_READY_POLL_LIMIT},
+    region::Regulation,
+    CommandStrobe, ConfigRegAddr, MarcState, PowerDownMode, ReadWrite, Status, StatusRegAddr,
+    TransferCmd,
+};
+
+pub struct AsyncCc1101<'a, P> {
+    port: &'a mut P,
+    shadow: Shadow,
+    verify: bool,
+    regulation: Option<Regulation>,
+}
+impl<'a, P: AsyncRead + AsyncWrite + Unpin> AsyncCc1101<'a, P> {
+    pub fn new(port: &'a mut P) -> Self {
+        Self {
+            port,
+            shadow: Shadow::default(),
+            verify: false,
+            regulation: None,
+        }
+    }
+
+    /// Write `cmd` followed by `data`, then read status byte and `ret.len()` bytes
+    async fn transfer(
+        &mut self,
+        cmd: TransferCmd,
+        data: &[u8],
+        ret: &mut [u8],
+    ) -> io::Result<Status> {
+        self.port.write_all(&cmd.0).await?;
+        self.port.write_all(data).await?;
+        let mut status = [0; 1];
+        self.port.read_exact(&mut status).await?;
+        self.port.read_exact(ret).await?;
+        Ok(Status(status[0]))
+    }
+    async fn read_raw(&mut self, cmd: TransferCmd) -> io::Result<(Status, u8)> {
+        let mut ret = [0];
+        let status = self.transfer(cmd, &[], &mut ret).await?;
+        Ok((status, ret[0]))
+    }
+    async fn read_raw_burst<const N: usize>(
+        &mut self,
+        cmd: TransferCmd,
+    ) -> io::Result<(Status, [u8; N])> {
+        let mut ret = [0; N];
+        let status = self.transfer(cmd, &[], &mut ret).await?;
+        Ok((status, ret))
+    }
+    async fn write_raw_burst<const N: usize>(
+        &mut self,
+        cmd: TransferCmd,
+        data: &[u8; N],
+    ) -> io::Result<(Status, [Status; N])> {
+        let mut ret = [0; N];
+        let status = self.transfer(cmd, data, &mut ret).await?;
+        Ok((status, arr_to_status(ret)))
+    }
+
+    /// See [`Cc1101::set_verify`](super::Cc1101::set_verify)
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+    /// See [`Cc1101::set_regulation`](super::Cc1101::set_regulation)
+    pub fn set_regulation(&mut self, regulation: Option<Regulation>) {
+        self.regulation = regulation;
+    }
+    pub fn shadow_config_reg(&self, addr: ConfigRegAddr) -> Option<u8> {
+        self.shadow.config[addr as usize]
+    }
+
+    pub async fn read_raw_status_reg(
+        &mut self,
+        close: bool,
+        addr: StatusRegAddr,
+    ) -> io::Result<(Status, u8)> {
+        self.read_raw(TransferCmd::status_reg(close, addr)).await
+    }
+    pub async fn read_marc_state(&mut self) -> io::Result<MarcState> {
+        let v = self
+            .read_raw_status_reg(true, StatusRegAddr::MARCSTATE)
+            .await?
+            .1
+            & 0x1f;
+        MarcState::try_from(v).map_err(|v| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid MARCSTATE {v:#04x}"),
+            )
+        })
+    }
+    pub async fn read_raw_config_reg(
+        &mut self,
+        close: bool,
+        addr: ConfigRegAddr,
+    ) -> io::Result<(Status, u8)> {
+        let ret = self
+            .read_raw(TransferCmd::config_reg(close, ReadWrite::Read, addr))
+            .await?;
+        self.shadow.update_config(addr, &[ret.1]);
+        Ok(ret)
+    }
+    pub async fn read_raw_config_burst<const N: usize>(
+        &mut self,
+        start_addr: ConfigRegAddr,
+    ) -> io::Result<(Status, [u8; N])> {
+        let ret = self
+            .read_raw_burst(TransferCmd::config_reg_burst(
+                ReadWrite::Read,
+                start_addr,
+                to_nonzero_u8::<N>().get(),
+            ))
+            .await?;
+        self.shadow.update_config(start_addr, &ret.1);
+        Ok(ret)
+    }
+    /// Result of `check` on the shadow copy, reading what it is [`Missing`] from the chip
+    async fn with_shadow<T>(
+        &mut self,
+        check: impl Fn(&Shadow) -> Result<T, Missing>,
+    ) -> io::Result<T> {
+        loop {
+            match check(&self.shadow) {
+                Ok(v) => return Ok(v),
+                Err(Missing::Config(addr)) => self.read_raw_config_reg(true, addr).await?.0,
+                Err(Missing::PaTable) => self.read_pa_table().await?.0,
+            };
+        }
+    }
+    async fn config_reg_value(&mut self, addr: ConfigRegAddr) -> io::Result<u8> {
+        self.with_shadow(|shadow| shadow.config_reg(addr)).await
+    }
+    async fn check_config_write(
+        &mut self,
+        start_addr: ConfigRegAddr,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let Some(regulation) = Regulation::enforced(self.regulation) else {
+            return Ok(());
+        };
+        Ok(self
+            .with_shadow(|shadow| regulation.check_config_write(shadow, start_addr, data))
+            .await??)
+    }
+    pub async fn write_raw_config_burst<const N: usize>(
+        &mut self,
+        start_addr: ConfigRegAddr,
+        data: &[u8; N],
+    ) -> io::Result<(Status, [Status; N])> {
+        self.check_config_write(start_addr, data).await?;
+        let ret = self
+            .write_raw_burst(
+                TransferCmd::config_reg_burst(
+                    ReadWrite::Write,
+                    start_addr,
+                    to_nonzero_u8::<N>().get(),
+                ),
+                data,
+            )
+            .await?;
+        self.shadow.update_config(start_addr, data);
+        if self.verify {
+            let read = self
+                .read_raw_burst::<N>(TransferCmd::config_reg_burst(
+                    ReadWrite::Read,
+                    start_addr,
+                    to_nonzero_u8::<N>().get(),
+                ))
+                .await?;
+            check_verify(start_addr, data, &read.1)?;
+        }
+        Ok(ret)
+    }
+    pub async fn write_raw_config_reg(
+        &mut self,
+        close: bool,
+        addr: ConfigRegAddr,
+        data: u8,
+    ) -> io::Result<(Status, Status)> {
+        self.check_config_write(addr, &[data]).await?;
+        let mut ret = [0];
+        let status = self
+            .transfer(
+                TransferCmd::config_reg(close, ReadWrite::Write, addr),
+                &[data],
+                &mut ret,
+            )
+            .await?;
+        self.shadow.update_config(addr, &[data]);
+        if self.verify {
+            let read = self
+                .read_raw(TransferCmd::config_reg(close, ReadWrite::Read, addr))
+                .await?;
+            check_verify(addr, &[data], &[read.1])?;
+        }
+        Ok((status, Status(ret[0])))
+    }
+    /// Replace bits selected by `mask` with `value`, using the shadow copy if known
+    pub async fn modify_config_reg(
+        &mut self,
+        addr: ConfigRegAddr,
+        mask: u8,
+        value: u8,
+    ) -> io::Result<Status> {
+        let old = self.config_reg_value(addr).await?;
+        Ok(self
+            .write_raw_config_reg(true, addr, old & !mask | value & mask)
+            .await?
+            .0)
+    }
+
+    pub async fn command_strobe(
+        &mut self,
+        close: bool,
+        rw: ReadWrite,
+        cmd: CommandStrobe,
+    ) -> io::Result<Status> {
+        let status = self
+            .transfer(TransferCmd::command_strobe(close, rw, cmd), &[], &mut [])
+            .await?;
+        self.shadow.update_strobe(cmd);
+        Ok(status)
+    }
+
+    pub async fn read_fifo(&mut self, close: bool) -> io::Result<(Status, u8)> {
+        self.read_raw(TransferCmd::fifo(close, ReadWrite::Read))
+            .await
+    }
+    pub async fn write_fifo(&mut self, close: bool, data: u8) -> io::Result<(Status, Status)> {
+        let mut ret = [0];
+        let status = self
+            .transfer(
+                TransferCmd::fifo(close, ReadWrite::Write),
+                &[data],
+                &mut ret,
+            )
+            .await?;
+        Ok((status, Status(ret[0])))
+    }
+    /// Burst read `buf.len()` bytes, which must be in `1..=64`
+    pub async fn read_fifo_into(&mut self, buf: &mut [u8]) -> io::Result<Status> {
+        if !(1..=64).contains(&buf.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fifo burst length out of range",
+            ));
+        }
+        self.transfer(
+            TransferCmd::fifo_burst(ReadWrite::Read, buf.len() as u8),
+            &[],
+            buf,
+        )
+        .await
+    }
+    /// Burst write `data`, which must have length in `1..=64`
+    pub async fn write_fifo_from(&mut self, data: &[u8]) -> io::Result<(Status, Vec<Status>)> {
+        if !(1..=64).contains(&data.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fifo burst length out of range",
+            ));
+        }
+        let mut ret = vec![0; data.len()];
+        let status = self
+            .transfer(
+                TransferCmd::fifo_burst(ReadWrite::Write, data.len() as u8),
+                data,
+                &mut ret,
+            )
+            .await?;
+        Ok((status, ret.into_iter().map(Status).collect()))
+    }
+
+    pub async fn read_pa_table(&mut self) -> io::Result<(Status, [u8; 8])> {
+        let ret = self
+            .read_raw_burst(TransferCmd::pa_table(ReadWrite::Read, 8))
+            .await?;
+        self.shadow.pa_table = Some(ret.1);
+        Ok(ret)
+    }
+    pub async fn write_pa_table(&mut self, data: &[u8; 8]) -> io::Result<(Status, [Status; 8])> {
+        if let Some(regulation) = Regulation::enforced(self.regulation) {
+            self.with_shadow(|shadow| regulation.check_pa_table_write(shadow, data))
+                .await??;
+        }
+        let ret = self
+            .write_raw_burst(TransferCmd::pa_table(ReadWrite::Write, 8), data)
+            .await?;
+        self.shadow.pa_table = Some(*data);
+        Ok(ret)
+    }
+
+    /// See [`Cc1101::wait_chip_ready`](super::Cc1101::wait_chip_ready)
+    pub async fn wait_chip_ready(&mut self) -> io::Result<Status> {
+        for _ in 0..CHIP_READY_POLL_LIMIT {
+            let status = self
+                .command_strobe(true, ReadWrite::Write, CommandStrobe::SNOP)
+                .await?;
+            if status.chip_ready() {
+                return Ok(status);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "CHIP_RDYn not asserted",
+        ))
+    }
+    /// See [`Cc1101::power_down`](super::Cc1101::power_down)
+    pub async fn power_down(&mut self, mode: PowerDownMode) -> io::Result<Status> {
+        self.command_strobe(false, ReadWrite::Write, CommandStrobe::SIDLE)
+            .await?;
+        self.command_strobe(
+            true,
+            ReadWrite::Write,
+            match mode {
+                PowerDownMode::Sleep => CommandStrobe::SPWD,
+                PowerDownMode::XoscOff => CommandStrobe::SXOFF,
+            },
+        )
+        .await
+    }
+    /// See [`Cc1101::wake`](super::Cc1101::wake)
+    pub async fn wake(&mut self) -> io::Result<Status> {
+        let mut status = self.wait_chip_ready().await?;
+        let shadow = self.shadow;
+        for (addr, v) in shadow.sleep_lost_regs() {
+            status = self.write_raw_config_reg(true, addr, v).await?.0;
+        }
+        if let Some(pa_table) = self.shadow.pa_table {
+            status = self.write_pa_table(&pa_table).await?.0;
+        }
+        Ok(status)
+    }
+
+    pub async fn exit(self) -> io::Result<()> {
+        self.port.write_all(&[0x00]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn duplex_transfer() {
+        let (mut host, mut device) = tokio::io::duplex(64);
+        let device = tokio::spawn(async move {
+            // burst write FREQ2..FREQ0
+            let mut req = [0; 5];
+            device.read_exact(&mut req).await.unwrap();
+            assert_eq!(req, [0x83, 0x4d, 0x10, 0xb0, 0x71]);
+            device.write_all(&[0x0f; 4]).await.unwrap();
+            // single read MDMCFG2
+            let mut req = [0; 2];
+            device.read_exact(&mut req).await.unwrap();
+            assert_eq!(req, [0xc0, 0x92]);
+            device.write_all(&[0x0f, 0x30]).await.unwrap();
+            let mut req = [0; 1];
+            device.read_exact(&mut req).await.unwrap();
+            assert_eq!(req, [0x00]);
+        });
+
+        let mut cc1101 = AsyncCc1101::new(&mut host);
+        cc1101
+            .write_raw_config_burst(ConfigRegAddr::FREQ2, &[0x10, 0xb0, 0x71])
+            .await
+            .unwrap();
+        assert_eq!(cc1101.shadow_config_reg(ConfigRegAddr::FREQ1), Some(0xb0));
+        let (_, v) = cc1101
+            .read_raw_config_reg(true, ConfigRegAddr::MDMCFG2)
+            .await
+            .unwrap();
+        assert_eq!(v, 0x30);
+        cc1101.exit().await.unwrap();
+        device.await.unwrap();
+    }
+}
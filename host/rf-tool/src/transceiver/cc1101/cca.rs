@@ -0,0 +1,209 @@
+//! Clear channel assessment and listen-before-talk transmission
+
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::Duration,
+};
+
+use rand::Rng;
+
+use super::{Cc1101, CommandStrobe, ConfigRegAddr, MarcState, ReadWrite, Status};
+
+/// `MCSM1.CCA_MODE`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CcaMode {
+    Always = 0,
+    /// If RSSI below threshold
+    RssiBelowThreshold = 1,
+    /// Unless currently receiving a packet
+    UnlessReceiving = 2,
+    /// If RSSI below threshold unless currently receiving a packet
+    RssiBelowThresholdUnlessReceiving = 3,
+}
+
+/// `AGCCTRL1.CARRIER_SENSE_REL_THR`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarrierSenseRelThr {
+    Disabled = 0,
+    Db6 = 1,
+    Db10 = 2,
+    Db14 = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CcaConfig {
+    pub mode: CcaMode,
+    pub rel_thr: CarrierSenseRelThr,
+    /// `AGCCTRL1.CARRIER_SENSE_ABS_THR` in dB relative to `MAGN_TARGET`, -7..=7,
+    /// `None` disables the absolute threshold
+    pub abs_thr: Option<i8>,
+}
+impl CcaConfig {
+    /// `CCA_MODE` field of `MCSM1`
+    pub fn mcsm1(&self) -> u8 {
+        (self.mode as u8) << 4
+    }
+    /// Carrier sense threshold fields of `AGCCTRL1`
+    pub fn agcctrl1(&self) -> u8 {
+        let abs_thr = match self.abs_thr {
+            Some(v) => (v.clamp(-7, 7) as u8) & 0x0f,
+            None => 0b1000,
+        };
+        (self.rel_thr as u8) << 4 | abs_thr
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LbtPolicy {
+    pub max_attempts: u32,
+    /// Backoff after first deferred attempt, doubled for each retry
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+impl Default for LbtPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LbtResult {
+    /// Channel was clear and the radio entered TX
+    Transmitted { attempts: u32 },
+    /// Channel stayed busy for all attempts, radio is still in RX
+    Deferred { attempts: u32 },
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    /// Maximum number of `MARCSTATE` polls while waiting for RX
+    const RX_POLL_LIMIT: usize = 100;
+
+    /// Update `MCSM1.CCA_MODE` and carrier sense thresholds in `AGCTRL1`
+    pub fn configure_cca(&mut self, config: &CcaConfig) -> io::Result<Status> {
+        self.modify_config_reg(ConfigRegAddr::MCSM1, 0x30, config.mcsm1())?;
+        self.modify_config_reg(ConfigRegAddr::AGCTRL1, 0x3f, config.agcctrl1())
+    }
+
+    fn wait_rx(&mut self) -> io::Result<()> {
+        for _ in 0..Self::RX_POLL_LIMIT {
+            if self.read_marc_state()? == MarcState::Rx {
+                return Ok(());
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "radio did not enter RX",
+        ))
+    }
+
+    /// Enter RX, then strobe `STX` so that the radio only transmits if CCA reports a clear
+    /// channel, retrying with randomized exponential backoff.
+    ///
+    /// Data is either loaded into TX FIFO before or fed through GDO0 in asynchronous serial
+    /// mode. CCA must be configured with [`Cc1101::configure_cca`]. `MCSM1.TXOFF_MODE` must not
+    /// be RX, as a transmission already finished would then look like a busy channel.
+    pub fn transmit_lbt(&mut self, policy: &LbtPolicy) -> io::Result<LbtResult> {
+        if self.config_reg_value(ConfigRegAddr::MCSM1)? & 0x03 == 0x03 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "listen-before-talk needs MCSM1.TXOFF_MODE other than RX",
+            ));
+        }
+        let mut backoff = policy.initial_backoff;
+        for attempt in 1..=policy.max_attempts {
+            self.command_strobe(true, ReadWrite::Write, CommandStrobe::SRX)?;
+            self.wait_rx()?;
+            self.command_strobe(true, ReadWrite::Write, CommandStrobe::STX)?;
+            // with a busy channel STX is ignored, anything but RX means it was accepted
+            if !self.read_marc_state()?.is_rx() {
+                return Ok(LbtResult::Transmitted { attempts: attempt });
+            }
+
+            if attempt != policy.max_attempts {
+                thread::sleep(backoff + backoff.mul_f64(rand::thread_rng().gen()));
+                backoff = (backoff * 2).min(policy.max_backoff);
+            }
+        }
+        Ok(LbtResult::Deferred {
+            attempts: policy.max_attempts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::fake::FakePort, *};
+
+    #[test]
+    fn register_fields() {
+        let config = CcaConfig {
+            mode: CcaMode::RssiBelowThresholdUnlessReceiving,
+            rel_thr: CarrierSenseRelThr::Db10,
+            abs_thr: Some(-3),
+        };
+        assert_eq!(config.mcsm1(), 0x30);
+        assert_eq!(config.agcctrl1(), 0x2d);
+        let config = CcaConfig {
+            mode: CcaMode::RssiBelowThreshold,
+            rel_thr: CarrierSenseRelThr::Disabled,
+            abs_thr: None,
+        };
+        assert_eq!((config.mcsm1(), config.agcctrl1()), (0x10, 0x08));
+        let config = CcaConfig {
+            abs_thr: Some(9),
+            ..config
+        };
+        assert_eq!(config.agcctrl1(), 0x07);
+
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        Cc1101::new(&mut port).configure_cca(&config).unwrap();
+        // MAGN_TARGET and AGC_LNA_PRIORITY are kept
+        assert_eq!(fake.chip().config_reg(ConfigRegAddr::MCSM1), 0x10);
+        assert_eq!(fake.chip().config_reg(ConfigRegAddr::AGCTRL1), 0x47);
+    }
+
+    #[test]
+    fn listen_before_talk() {
+        let policy = LbtPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        };
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        let mut cc1101 = Cc1101::new(&mut port);
+
+        // a short transmission has already ended in IDLE when MARCSTATE is read
+        assert_eq!(
+            cc1101.transmit_lbt(&policy).unwrap(),
+            LbtResult::Transmitted { attempts: 1 }
+        );
+        fake.chip().tx_reads = 5;
+        assert_eq!(
+            cc1101.transmit_lbt(&policy).unwrap(),
+            LbtResult::Transmitted { attempts: 1 }
+        );
+
+        fake.chip().busy = true;
+        assert_eq!(
+            cc1101.transmit_lbt(&policy).unwrap(),
+            LbtResult::Deferred { attempts: 3 }
+        );
+
+        cc1101
+            .modify_config_reg(ConfigRegAddr::MCSM1, 0x03, 0x03)
+            .unwrap();
+        assert_eq!(
+            cc1101.transmit_lbt(&policy).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Self test for bringing up new boards
+
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+};
+
+use super::{Cc1101, CommandStrobe, ConfigRegAddr, MarcState, ReadWrite, StatusRegAddr};
+
+pub const EXPECTED_PARTNUM: u8 = 0x00;
+pub const EXPECTED_VERSION: u8 = 0x14;
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    /// Failure reason
+    pub result: Result<(), String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub checks: Vec<Check>,
+}
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.result.is_ok())
+    }
+    fn push(&mut self, name: &'static str, failures: Vec<String>) {
+        self.checks.push(Check {
+            name,
+            result: if failures.is_empty() {
+                Ok(())
+            } else {
+                Err(failures.join("; "))
+            },
+        })
+    }
+}
+impl Display for SelfTestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            match &check.result {
+                Ok(()) => writeln!(f, "[PASS] {}", check.name)?,
+                Err(e) => writeln!(f, "[FAIL] {}: {e}", check.name)?,
+            }
+        }
+        let failed = self.checks.iter().filter(|c| c.result.is_err()).count();
+        write!(
+            f,
+            "{} checks; {} passed; {failed} failed",
+            self.checks.len(),
+            self.checks.len() - failed
+        )
+    }
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    /// Maximum number of `MARCSTATE` polls while waiting for calibration
+    const CALIBRATION_POLL_LIMIT: usize = 100;
+
+    fn check_part(&mut self) -> io::Result<Vec<String>> {
+        let mut failures = Vec::new();
+        for (addr, expected) in [
+            (StatusRegAddr::PARTNUM, EXPECTED_PARTNUM),
+            (StatusRegAddr::VERSION, EXPECTED_VERSION),
+        ] {
+            let v = self.read_raw_status_reg(true, addr)?.1;
+            if v != expected {
+                failures.push(format!("{addr:?} is {v:#04x}, expected {expected:#04x}"));
+            }
+        }
+        Ok(failures)
+    }
+
+    fn check_config_patterns(&mut self) -> io::Result<Vec<String>> {
+        let mut failures = Vec::new();
+        for addr in ConfigRegAddr::ALL {
+            if addr.is_test_only() {
+                continue;
+            }
+            let mask = addr.writable_mask();
+            for pattern in [0x55, 0xaa, 0x00, 0xff] {
+                self.write_raw_config_reg(true, addr, pattern & mask)?;
+                let v = self.read_raw_config_reg(true, addr)?.1 & mask;
+                if v != pattern & mask {
+                    failures.push(format!(
+                        "{addr:?} written {:#04x} read {v:#04x}",
+                        pattern & mask
+                    ));
+                }
+            }
+        }
+        Ok(failures)
+    }
+
+    fn check_reset_defaults(&mut self) -> io::Result<Vec<String>> {
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SRES)?;
+        self.wait_chip_ready()?;
+        let values = self
+            .read_raw_config_burst::<{ ConfigRegAddr::ALL.len() }>(ConfigRegAddr::IOCFG2)?
+            .1;
+        Ok(ConfigRegAddr::ALL
+            .into_iter()
+            .zip(values)
+            .filter(|(addr, v)| v & addr.writable_mask() != addr.reset_value())
+            .map(|(addr, v)| {
+                format!(
+                    "{addr:?} is {v:#04x} after reset, expected {:#04x}",
+                    addr.reset_value()
+                )
+            })
+            .collect())
+    }
+
+    fn check_calibration(&mut self) -> io::Result<Vec<String>> {
+        let mut failures = Vec::new();
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SIDLE)?;
+        let state = self.read_marc_state()?;
+        if state != MarcState::Idle {
+            failures.push(format!("{state:?} after SIDLE"));
+            return Ok(failures);
+        }
+
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SCAL)?;
+        for _ in 0..Self::CALIBRATION_POLL_LIMIT {
+            match self.read_marc_state()? {
+                MarcState::Idle => return Ok(failures),
+                MarcState::VcoonMc
+                | MarcState::RegonMc
+                | MarcState::Mancal
+                | MarcState::Startcal
+                | MarcState::Endcal => (),
+                state => {
+                    failures.push(format!("unexpected {state:?} during SCAL"));
+                    return Ok(failures);
+                }
+            }
+        }
+        failures.push("SCAL did not return to IDLE".to_owned());
+        Ok(failures)
+    }
+
+    fn check_fifo_flush(&mut self) -> io::Result<Vec<String>> {
+        let mut failures = Vec::new();
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SIDLE)?;
+
+        self.write_fifo_burst(&[0x12, 0x34, 0x56, 0x78])?;
+        let tx_bytes = self.read_raw_status_reg(true, StatusRegAddr::TXBYTES)?.1;
+        if tx_bytes != 4 {
+            failures.push(format!("TXBYTES is {tx_bytes:#04x} after writing 4 bytes"));
+        }
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SFTX)?;
+        let tx_bytes = self.read_raw_status_reg(true, StatusRegAddr::TXBYTES)?.1;
+        if tx_bytes != 0 {
+            failures.push(format!("TXBYTES is {tx_bytes:#04x} after SFTX"));
+        }
+
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SFRX)?;
+        let rx_bytes = self.read_raw_status_reg(true, StatusRegAddr::RXBYTES)?.1;
+        if rx_bytes != 0 {
+            failures.push(format!("RXBYTES is {rx_bytes:#04x} after SFRX"));
+        }
+        Ok(failures)
+    }
+
+    /// Run all checks and reset the chip afterwards, so configuration has to be written again.
+    /// The regulation is not applied to the test patterns.
+    pub fn self_test(&mut self) -> io::Result<SelfTestReport> {
+        // patterns go through frequencies and PATABLE values outside any region, and nothing is
+        // transmitted before the reset at the end
+        let regulation = self.regulation.take();
+        let report = self.run_self_test();
+        self.regulation = regulation;
+        report
+    }
+    fn run_self_test(&mut self) -> io::Result<SelfTestReport> {
+        let mut report = SelfTestReport::default();
+        report.push("part number and version", self.check_part()?);
+        report.push("config register patterns", self.check_config_patterns()?);
+        report.push("reset defaults", self.check_reset_defaults()?);
+        report.push("calibration", self.check_calibration()?);
+        report.push("fifo flush", self.check_fifo_flush()?);
+
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SRES)?;
+        self.wait_chip_ready()?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            fake::FakePort,
+            region::{Regulation, ISM_433},
+        },
+        *,
+    };
+
+    #[test]
+    fn self_test_with_regulation() {
+        let mut port = FakePort::default();
+        let mut cc1101 = Cc1101::new(&mut port);
+        let regulation = Regulation::new(&ISM_433, 26_000_000);
+        cc1101.set_regulation(Some(regulation));
+        let report = cc1101.self_test().unwrap();
+        assert_eq!(report.checks.len(), 5);
+        assert_eq!(report.checks[1].name, "config register patterns");
+        assert_eq!(report.checks[1].result, Ok(()));
+        assert_eq!(cc1101.regulation(), Some(regulation));
+    }
+}
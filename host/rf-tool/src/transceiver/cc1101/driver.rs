@@ -0,0 +1,598 @@
+//! Blocking driver over [`std::io`]
+
+use std::{
+    io::{self, Read, Write},
+    num::NonZeroU8,
+};
+
+use super::{
+    region::{self, carrier_freq, FREQ_REGS},
+    CommandStrobe, ConfigRegAddr, MarcState, ReadWrite, Status, StatusRegAddr, TransferCmd,
+};
+
+#[inline]
+pub(super) const fn to_nonzero_u8<const N: usize>() -> NonZeroU8 {
+    if N > 128 {
+        panic!()
+    } else {
+        match NonZeroU8::new(N as u8) {
+            Some(v) => v,
+            None => panic!(),
+        }
+    }
+}
+
+#[inline]
+pub(super) fn arr_to_status<const N: usize>(arr: [u8; N]) -> [Status; N] {
+    std::array::from_fn(|idx| Status(arr[idx]))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerDownMode {
+    /// `SPWD`: enter SLEEP, PATABLE and `TEST2..TEST0` are lost
+    Sleep,
+    /// `SXOFF`: turn off crystal oscillator, all registers are retained
+    XoscOff,
+}
+
+/// Number of config registers, `IOCFG2..=TEST0`
+const CONFIG_REG_COUNT: usize = ConfigRegAddr::ALL.len();
+
+/// Maximum number of `SNOP` polls in [`Cc1101::wait_chip_ready`] before giving up
+pub(super) const CHIP_READY_POLL_LIMIT: usize = 1000;
+
+/// Config registers lost in SLEEP, PATABLE is lost as well
+pub(super) const SLEEP_LOST_REGS: [ConfigRegAddr; 3] = [
+    ConfigRegAddr::TEST2,
+    ConfigRegAddr::TEST1,
+    ConfigRegAddr::TEST0,
+];
+
+/// Value a check needs which the shadow copy does not have, read it and check again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Missing {
+    Config(ConfigRegAddr),
+    PaTable,
+}
+
+/// Host side copy of register values written to or read from the chip
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Shadow {
+    pub(super) config: [Option<u8>; CONFIG_REG_COUNT],
+    pub(super) pa_table: Option<[u8; 8]>,
+}
+impl Default for Shadow {
+    fn default() -> Self {
+        Self {
+            config: [None; CONFIG_REG_COUNT],
+            pa_table: None,
+        }
+    }
+}
+/// Parts of the drivers not doing I/O, shared by [`Cc1101`] and the async driver
+impl Shadow {
+    pub(super) fn update_config(&mut self, start_addr: ConfigRegAddr, data: &[u8]) {
+        for (reg, v) in self.config[start_addr as usize..].iter_mut().zip(data) {
+            *reg = Some(*v);
+        }
+    }
+    pub(super) fn config_reg(&self, addr: ConfigRegAddr) -> Result<u8, Missing> {
+        self.config[addr as usize].ok_or(Missing::Config(addr))
+    }
+    pub(super) fn pa_table(&self) -> Result<[u8; 8], Missing> {
+        self.pa_table.ok_or(Missing::PaTable)
+    }
+    /// Values of [`SLEEP_LOST_REGS`] to write back after SLEEP
+    pub(super) fn sleep_lost_regs(&self) -> impl Iterator<Item = (ConfigRegAddr, u8)> + '_ {
+        SLEEP_LOST_REGS
+            .into_iter()
+            .filter_map(|addr| Some((addr, self.config[addr as usize]?)))
+    }
+    /// Forget registers reset by `cmd`
+    pub(super) fn update_strobe(&mut self, cmd: CommandStrobe) {
+        if cmd == CommandStrobe::SRES {
+            *self = Self::default();
+        }
+    }
+    /// Carrier frequency after writing `data` at `start_addr`, other registers of
+    /// [`FREQ_REGS`] from the shadow copy
+    pub(super) fn carrier_after_write(
+        &self,
+        xosc_freq: u32,
+        start_addr: ConfigRegAddr,
+        data: &[u8],
+    ) -> Result<u64, Missing> {
+        let mut values = [0; FREQ_REGS.len()];
+        for (value, addr) in values.iter_mut().zip(FREQ_REGS) {
+            *value = match (addr as usize).checked_sub(start_addr as usize) {
+                Some(idx) if idx < data.len() => data[idx],
+                _ => self.config_reg(addr)?,
+            };
+        }
+        Ok(carrier_freq(xosc_freq, values))
+    }
+}
+
+/// Value read back in verify mode differs from value written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyError {
+    pub addr: ConfigRegAddr,
+    pub written: u8,
+    pub read: u8,
+}
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} written {:#04x} but read back {:#04x}",
+            self.addr, self.written, self.read
+        )
+    }
+}
+impl std::error::Error for VerifyError {}
+
+/// Compare the writable bits of config registers read back after writing them at
+/// `start_addr`. `FSCAL3..FSCAL0` are skipped as calibration may update them at any time.
+pub(super) fn check_verify(
+    start_addr: ConfigRegAddr,
+    written: &[u8],
+    read: &[u8],
+) -> io::Result<()> {
+    let regs = ConfigRegAddr::ALL[start_addr as usize..].iter();
+    for ((addr, written), read) in regs.zip(written).zip(read) {
+        let mask = addr.writable_mask();
+        if !addr.is_calibration() && written & mask != read & mask {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                VerifyError {
+                    addr: *addr,
+                    written: *written,
+                    read: *read,
+                },
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Blocking CC1101 driver, create with [`Cc1101::new`]
+pub struct Cc1101<'a, P> {
+    port: &'a mut P,
+    pub(super) shadow: Shadow,
+    verify: bool,
+    pub(super) regulation: Option<region::Regulation>,
+}
+impl<'a, P: Read + Write> From<&'a mut P> for Cc1101<'a, P> {
+    fn from(port: &'a mut P) -> Self {
+        Self::new(port)
+    }
+}
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    pub fn new(port: &'a mut P) -> Self {
+        Self {
+            port,
+            shadow: Shadow::default(),
+            verify: false,
+            regulation: None,
+        }
+    }
+
+    fn read_raw(&mut self, cmd: TransferCmd) -> io::Result<(Status, u8)> {
+        self.port.write_all(&cmd.0)?;
+        let mut buf = [0, 0];
+        self.port.read_exact(&mut buf)?;
+        Ok((Status(buf[0]), buf[1]))
+    }
+    fn read_raw_burst<const N: usize>(
+        &mut self,
+        cmd: TransferCmd,
+    ) -> io::Result<(Status, [u8; N])> {
+        self.port.write_all(&cmd.0)?;
+        let mut status = [0; 1];
+        let mut ret = [0; N];
+        self.port.read_exact(&mut status)?;
+        self.port.read_exact(&mut ret)?;
+        Ok((Status(status[0]), ret))
+    }
+    fn read_raw_burst_into(&mut self, cmd: TransferCmd, buf: &mut [u8]) -> io::Result<Status> {
+        self.port.write_all(&cmd.0)?;
+        let mut status = [0; 1];
+        self.port.read_exact(&mut status)?;
+        self.port.read_exact(buf)?;
+        Ok(Status(status[0]))
+    }
+    fn write_raw(&mut self, cmd: TransferCmd, data: u8) -> io::Result<(Status, Status)> {
+        self.port.write_all(&[cmd.0[0], cmd.0[1], data])?;
+        let mut buf = [0, 0];
+        self.port.read_exact(&mut buf)?;
+        Ok((Status(buf[0]), Status(buf[1])))
+    }
+    fn write_raw_burst<const N: usize>(
+        &mut self,
+        cmd: TransferCmd,
+        data: &[u8; N],
+    ) -> io::Result<(Status, [Status; N])> {
+        self.port.write_all(&cmd.0)?;
+        self.port.write_all(data)?;
+        let mut status = [0; 1];
+        let mut ret = [0; N];
+        self.port.read_exact(&mut status)?;
+        self.port.read_exact(&mut ret)?;
+        Ok((Status(status[0]), arr_to_status(ret)))
+    }
+    fn write_raw_burst_from(
+        &mut self,
+        cmd: TransferCmd,
+        data: &[u8],
+    ) -> io::Result<(Status, Vec<Status>)> {
+        self.port.write_all(&cmd.0)?;
+        self.port.write_all(data)?;
+        let mut status = [0; 1];
+        let mut ret = vec![0; data.len()];
+        self.port.read_exact(&mut status)?;
+        self.port.read_exact(&mut ret)?;
+        Ok((Status(status[0]), ret.into_iter().map(Status).collect()))
+    }
+
+    pub fn read_raw_status_reg(
+        &mut self,
+        close: bool,
+        addr: StatusRegAddr,
+    ) -> io::Result<(Status, u8)> {
+        self.read_raw(TransferCmd::status_reg(close, addr))
+    }
+    pub fn read_marc_state(&mut self) -> io::Result<MarcState> {
+        let v = self.read_raw_status_reg(true, StatusRegAddr::MARCSTATE)?.1 & 0x1f;
+        MarcState::try_from(v).map_err(|v| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid MARCSTATE {v:#04x}"),
+            )
+        })
+    }
+    pub fn read_raw_config_reg(
+        &mut self,
+        close: bool,
+        addr: ConfigRegAddr,
+    ) -> io::Result<(Status, u8)> {
+        let ret = self.read_raw(TransferCmd::config_reg(close, ReadWrite::Read, addr))?;
+        self.shadow.update_config(addr, &[ret.1]);
+        Ok(ret)
+    }
+    pub fn read_raw_config_burst<const N: usize>(
+        &mut self,
+        start_addr: ConfigRegAddr,
+    ) -> io::Result<(Status, [u8; N])> {
+        let ret = self.read_raw_burst(TransferCmd::config_reg_burst(
+            ReadWrite::Read,
+            start_addr,
+            to_nonzero_u8::<N>().get(),
+        ))?;
+        self.shadow.update_config(start_addr, &ret.1);
+        Ok(ret)
+    }
+    pub fn write_raw_config_burst<const N: usize>(
+        &mut self,
+        start_addr: ConfigRegAddr,
+        data: &[u8; N],
+    ) -> io::Result<(Status, [Status; N])> {
+        self.check_config_write(start_addr, data)?;
+        self.write_config_burst(start_addr, data)
+    }
+    pub(super) fn write_config_burst<const N: usize>(
+        &mut self,
+        start_addr: ConfigRegAddr,
+        data: &[u8; N],
+    ) -> io::Result<(Status, [Status; N])> {
+        let ret = self.write_raw_burst(
+            TransferCmd::config_reg_burst(ReadWrite::Write, start_addr, to_nonzero_u8::<N>().get()),
+            data,
+        )?;
+        self.shadow.update_config(start_addr, data);
+        if self.verify {
+            let read = self.read_raw_burst::<N>(TransferCmd::config_reg_burst(
+                ReadWrite::Read,
+                start_addr,
+                to_nonzero_u8::<N>().get(),
+            ))?;
+            check_verify(start_addr, data, &read.1)?;
+        }
+        Ok(ret)
+    }
+    pub fn write_raw_config_reg(
+        &mut self,
+        close: bool,
+        addr: ConfigRegAddr,
+        data: u8,
+    ) -> io::Result<(Status, Status)> {
+        self.check_config_write(addr, &[data])?;
+        let ret = self.write_raw(TransferCmd::config_reg(close, ReadWrite::Write, addr), data)?;
+        self.shadow.update_config(addr, &[data]);
+        if self.verify {
+            let read = self.read_raw(TransferCmd::config_reg(close, ReadWrite::Read, addr))?;
+            check_verify(addr, &[data], &[read.1])?;
+        }
+        Ok(ret)
+    }
+
+    /// Read back every config register write and fail with [`VerifyError`] if a writable bit
+    /// differs
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+    /// Last value written to or read from a config register, `None` if unknown.
+    ///
+    /// Registers updated by the chip itself, like `FSCAL3..FSCAL0` after calibration, may be
+    /// stale.
+    pub fn shadow_config_reg(&self, addr: ConfigRegAddr) -> Option<u8> {
+        self.shadow.config[addr as usize]
+    }
+    /// Result of `check` on the shadow copy, reading what it is [`Missing`] from the chip
+    pub(super) fn with_shadow<T>(
+        &mut self,
+        check: impl Fn(&Shadow) -> Result<T, Missing>,
+    ) -> io::Result<T> {
+        loop {
+            match check(&self.shadow) {
+                Ok(v) => return Ok(v),
+                Err(Missing::Config(addr)) => self.read_raw_config_reg(true, addr)?.0,
+                Err(Missing::PaTable) => self.read_pa_table()?.0,
+            };
+        }
+    }
+    /// Value from the shadow copy if known, otherwise read from the chip
+    pub(super) fn config_reg_value(&mut self, addr: ConfigRegAddr) -> io::Result<u8> {
+        self.with_shadow(|shadow| shadow.config_reg(addr))
+    }
+    /// Replace bits selected by `mask` with `value`, using the shadow copy if known
+    pub fn modify_config_reg(
+        &mut self,
+        addr: ConfigRegAddr,
+        mask: u8,
+        value: u8,
+    ) -> io::Result<Status> {
+        let old = self.config_reg_value(addr)?;
+        Ok(self
+            .write_raw_config_reg(true, addr, old & !mask | value & mask)?
+            .0)
+    }
+
+    pub fn command_strobe(
+        &mut self,
+        close: bool,
+        rw: ReadWrite,
+        cmd: CommandStrobe,
+    ) -> io::Result<Status> {
+        self.port
+            .write_all(&TransferCmd::command_strobe(close, rw, cmd).0)?;
+        let mut buf = [0];
+        self.port.read_exact(&mut buf)?;
+        self.shadow.update_strobe(cmd);
+        Ok(Status(buf[0]))
+    }
+
+    pub fn read_fifo(&mut self, close: bool) -> io::Result<(Status, u8)> {
+        self.read_raw(TransferCmd::fifo(close, ReadWrite::Read))
+    }
+    pub fn read_fifo_burst<const N: usize>(&mut self) -> io::Result<(Status, [u8; N])> {
+        self.read_raw_burst(TransferCmd::fifo_burst(
+            ReadWrite::Read,
+            to_nonzero_u8::<N>().get(),
+        ))
+    }
+    /// Burst read `buf.len()` bytes, which must be in `1..=64`
+    pub fn read_fifo_into(&mut self, buf: &mut [u8]) -> io::Result<Status> {
+        if !(1..=64).contains(&buf.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fifo burst length out of range",
+            ));
+        }
+        self.read_raw_burst_into(
+            TransferCmd::fifo_burst(ReadWrite::Read, buf.len() as u8),
+            buf,
+        )
+    }
+    pub fn write_fifo(&mut self, close: bool, data: u8) -> io::Result<(Status, Status)> {
+        self.write_raw(TransferCmd::fifo(close, ReadWrite::Write), data)
+    }
+    /// Burst write `data`, which must have length in `1..=64`
+    pub fn write_fifo_from(&mut self, data: &[u8]) -> io::Result<(Status, Vec<Status>)> {
+        if !(1..=64).contains(&data.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fifo burst length out of range",
+            ));
+        }
+        self.write_raw_burst_from(
+            TransferCmd::fifo_burst(ReadWrite::Write, data.len() as u8),
+            data,
+        )
+    }
+    pub fn write_fifo_burst<const N: usize>(
+        &mut self,
+        data: &[u8; N],
+    ) -> io::Result<(Status, [Status; N])> {
+        self.write_raw_burst(
+            TransferCmd::fifo_burst(ReadWrite::Write, to_nonzero_u8::<N>().get()),
+            data,
+        )
+    }
+
+    pub fn read_pa_table(&mut self) -> io::Result<(Status, [u8; 8])> {
+        let ret = self.read_raw_burst(TransferCmd::pa_table(ReadWrite::Read, 8))?;
+        self.shadow.pa_table = Some(ret.1);
+        Ok(ret)
+    }
+    pub fn write_pa_table(&mut self, data: &[u8; 8]) -> io::Result<(Status, [Status; 8])> {
+        self.check_pa_table_write(data)?;
+        let ret = self.write_raw_burst(TransferCmd::pa_table(ReadWrite::Write, 8), data)?;
+        self.shadow.pa_table = Some(*data);
+        Ok(ret)
+    }
+
+    /// Strobe `SNOP` until `CHIP_RDYn` goes low
+    pub fn wait_chip_ready(&mut self) -> io::Result<Status> {
+        for _ in 0..CHIP_READY_POLL_LIMIT {
+            let status = self.command_strobe(true, ReadWrite::Write, CommandStrobe::SNOP)?;
+            if status.chip_ready() {
+                return Ok(status);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "CHIP_RDYn not asserted",
+        ))
+    }
+    /// Go to IDLE, then enter SLEEP or XOFF once CSn goes high.
+    pub fn power_down(&mut self, mode: PowerDownMode) -> io::Result<Status> {
+        self.command_strobe(false, ReadWrite::Write, CommandStrobe::SIDLE)?;
+        self.command_strobe(
+            true,
+            ReadWrite::Write,
+            match mode {
+                PowerDownMode::Sleep => CommandStrobe::SPWD,
+                PowerDownMode::XoscOff => CommandStrobe::SXOFF,
+            },
+        )
+    }
+    /// Pull CSn low and wait until `CHIP_RDYn` goes low, then restore PATABLE and
+    /// `TEST2..TEST0`, which are lost in SLEEP, from the shadow copy.
+    pub fn wake(&mut self) -> io::Result<Status> {
+        let mut status = self.wait_chip_ready()?;
+        let shadow = self.shadow;
+        for (addr, v) in shadow.sleep_lost_regs() {
+            status = self.write_raw_config_reg(true, addr, v)?.0;
+        }
+        if let Some(pa_table) = self.shadow.pa_table {
+            status = self.write_pa_table(&pa_table)?.0;
+        }
+        Ok(status)
+    }
+
+    pub fn exit(self) -> io::Result<()> {
+        self.port.write_all(&[0x00])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::fake::FakePort, *};
+
+    #[test]
+    fn wake_restores_lost_registers() {
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        let mut cc1101 = Cc1101::from(&mut port);
+        let pa_table = [0x12, 0x0e, 0x1d, 0x34, 0x60, 0x84, 0xc8, 0xc0];
+        cc1101.write_pa_table(&pa_table).unwrap();
+        cc1101
+            .write_raw_config_burst(ConfigRegAddr::TEST2, &[0x81, 0x35, 0x09])
+            .unwrap();
+
+        cc1101.power_down(PowerDownMode::Sleep).unwrap();
+        assert_eq!(fake.chip().config_reg(ConfigRegAddr::TEST2), 0x88);
+        assert_ne!(fake.chip().pa_table, pa_table);
+        assert!(cc1101.wake().unwrap().chip_ready());
+        let chip = fake.chip();
+        assert_eq!(
+            chip.strobes,
+            [
+                CommandStrobe::SIDLE,
+                CommandStrobe::SPWD,
+                CommandStrobe::SNOP,
+                CommandStrobe::SNOP,
+                CommandStrobe::SNOP,
+                CommandStrobe::SNOP,
+            ]
+        );
+        assert_eq!(chip.pa_table, pa_table);
+        assert_eq!(
+            [
+                ConfigRegAddr::TEST2,
+                ConfigRegAddr::TEST1,
+                ConfigRegAddr::TEST0
+            ]
+            .map(|addr| chip.config_reg(addr)),
+            [0x81, 0x35, 0x09]
+        );
+    }
+
+    #[test]
+    fn shadow_and_verify() {
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        let mut cc1101 = Cc1101::new(&mut port);
+        assert_eq!(cc1101.shadow_config_reg(ConfigRegAddr::MDMCFG2), None);
+        cc1101
+            .read_raw_config_burst::<3>(ConfigRegAddr::MDMCFG2)
+            .unwrap();
+        assert_eq!(cc1101.shadow_config_reg(ConfigRegAddr::MDMCFG0), Some(0xf8));
+        // shadow copy is used instead of reading again
+        cc1101
+            .modify_config_reg(ConfigRegAddr::MDMCFG2, 0x70, 0x30)
+            .unwrap();
+        assert_eq!(fake.chip().reads.len(), 3);
+        assert_eq!(fake.chip().config_reg(ConfigRegAddr::MDMCFG2), 0x32);
+        cc1101
+            .command_strobe(true, ReadWrite::Write, CommandStrobe::SRES)
+            .unwrap();
+        assert_eq!(cc1101.shadow_config_reg(ConfigRegAddr::MDMCFG2), None);
+
+        cc1101.set_verify(true);
+        // unused bits read back as 0
+        cc1101
+            .write_raw_config_reg(true, ConfigRegAddr::IOCFG2, 0xff)
+            .unwrap();
+        cc1101
+            .write_raw_config_burst(ConfigRegAddr::MCSM2, &[0xff, 0x30, 0x18])
+            .unwrap();
+        // calibration is not verified
+        fake.chip().stuck = vec![ConfigRegAddr::FSCAL1, ConfigRegAddr::FREQ1];
+        cc1101
+            .write_raw_config_reg(true, ConfigRegAddr::FSCAL1, 0x11)
+            .unwrap();
+        let err = cc1101
+            .write_raw_config_burst(ConfigRegAddr::FREQ2, &[0x10, 0xb0, 0x71])
+            .unwrap_err();
+        assert_eq!(
+            err.into_inner().unwrap().downcast_ref::<VerifyError>(),
+            Some(&VerifyError {
+                addr: ConfigRegAddr::FREQ1,
+                written: 0xb0,
+                read: 0xc4,
+            })
+        );
+    }
+
+    #[test]
+    fn shadow_carrier() {
+        let mut shadow = Shadow::default();
+        let freq = [0x10, 0xb0, 0x71];
+        assert_eq!(
+            shadow.carrier_after_write(26_000_000, ConfigRegAddr::FREQ2, &freq),
+            Err(Missing::Config(ConfigRegAddr::CHANNR))
+        );
+        shadow.update_config(ConfigRegAddr::CHANNR, &[0]);
+        shadow.update_config(ConfigRegAddr::MDMCFG1, &[0x22, 0xf8]);
+        assert_eq!(
+            shadow.carrier_after_write(26_000_000, ConfigRegAddr::FREQ2, &freq),
+            Ok(433_919_830)
+        );
+        shadow.update_strobe(CommandStrobe::SRES);
+        assert_eq!(shadow.config, [None; CONFIG_REG_COUNT]);
+    }
+
+    #[test]
+    fn chip_ready_timeout() {
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        fake.chip().not_ready = usize::MAX;
+        let mut cc1101 = Cc1101::new(&mut port);
+        assert_eq!(
+            cc1101.wait_chip_ready().unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert_eq!(fake.chip().strobes.len(), 1000);
+    }
+}
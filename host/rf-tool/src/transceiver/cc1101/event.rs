@@ -0,0 +1,217 @@
+//! Packet events by polling `PKTSTATUS`, as GDO levels are not reported by the FPGA
+
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, Read, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Cc1101, StatusRegAddr};
+
+/// Value of `PKTSTATUS`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PacketStatus(pub u8);
+impl PacketStatus {
+    /// The last CRC comparison matched. Cleared when entering/restarting RX mode.
+    pub const fn crc_ok(self) -> bool {
+        self.0 & 0x80 != 0
+    }
+    /// Carrier sense. Cleared when entering IDLE mode.
+    pub const fn carrier_sense(self) -> bool {
+        self.0 & 0x40 != 0
+    }
+    /// Preamble Quality reached
+    pub const fn pqt_reached(self) -> bool {
+        self.0 & 0x20 != 0
+    }
+    /// Channel is clear
+    pub const fn cca(self) -> bool {
+        self.0 & 0x10 != 0
+    }
+    /// Start of Frame Delimiter. Asserted when sync word has been received and de-asserted at
+    /// the end of the packet. Also de-asserts on RX abort.
+    pub const fn sfd(self) -> bool {
+        self.0 & 0x08 != 0
+    }
+    /// Current `GDO2` value
+    pub const fn gdo2(self) -> bool {
+        self.0 & 0x04 != 0
+    }
+    /// Current `GDO0` value
+    pub const fn gdo0(self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    /// Events for changes from `prev` to `self`
+    pub fn events_since(self, prev: Self) -> impl Iterator<Item = PacketEvent> {
+        let edge = |f: fn(Self) -> bool| (f(prev) != f(self)).then_some(f(self));
+        [
+            edge(Self::carrier_sense).map(|v| {
+                if v {
+                    PacketEvent::CarrierSensed
+                } else {
+                    PacketEvent::CarrierLost
+                }
+            }),
+            edge(Self::cca).map(|v| {
+                if v {
+                    PacketEvent::ChannelClear
+                } else {
+                    PacketEvent::ChannelBusy
+                }
+            }),
+            edge(Self::pqt_reached)
+                .filter(|v| *v)
+                .map(|_| PacketEvent::PreambleQualityReached),
+            edge(Self::sfd).map(|v| {
+                if v {
+                    PacketEvent::SyncDetected
+                } else {
+                    PacketEvent::PacketDone {
+                        crc_ok: self.crc_ok(),
+                    }
+                }
+            }),
+            edge(Self::gdo0).map(PacketEvent::Gdo0),
+            edge(Self::gdo2).map(PacketEvent::Gdo2),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+impl Debug for PacketStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketStatus")
+            .field("crc_ok", &self.crc_ok())
+            .field("carrier_sense", &self.carrier_sense())
+            .field("pqt_reached", &self.pqt_reached())
+            .field("cca", &self.cca())
+            .field("sfd", &self.sfd())
+            .field("gdo2", &self.gdo2())
+            .field("gdo0", &self.gdo0())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketEvent {
+    CarrierSensed,
+    CarrierLost,
+    ChannelClear,
+    ChannelBusy,
+    PreambleQualityReached,
+    SyncDetected,
+    /// End of packet or RX abort after sync word
+    PacketDone {
+        crc_ok: bool,
+    },
+    /// `GDO0` level changed
+    Gdo0(bool),
+    /// `GDO2` level changed
+    Gdo2(bool),
+}
+
+/// Blocking iterator polling `PKTSTATUS`, created by [`Cc1101::packet_events`]
+pub struct PacketEvents<'c, 'a, P> {
+    cc1101: &'c mut Cc1101<'a, P>,
+    interval: Duration,
+    last: Option<PacketStatus>,
+    pending: VecDeque<PacketEvent>,
+}
+impl<'c, 'a, P: Read + Write> PacketEvents<'c, 'a, P> {
+    /// Status seen at the last poll
+    pub fn last_status(&self) -> Option<PacketStatus> {
+        self.last
+    }
+
+    fn poll(&mut self) -> io::Result<()> {
+        let status = self.cc1101.read_packet_status()?;
+        if let Some(last) = self.last {
+            self.pending.extend(status.events_since(last));
+        }
+        self.last = Some(status);
+        Ok(())
+    }
+
+    /// Wait for first event matching `pred`, `None` on timeout
+    pub fn wait_for(
+        &mut self,
+        timeout: Duration,
+        mut pred: impl FnMut(&PacketEvent) -> bool,
+    ) -> io::Result<Option<PacketEvent>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            while let Some(event) = self.pending.pop_front() {
+                if pred(&event) {
+                    return Ok(Some(event));
+                }
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(self.interval);
+            self.poll()?;
+        }
+    }
+}
+impl<'c, 'a, P: Read + Write> Iterator for PacketEvents<'c, 'a, P> {
+    type Item = io::Result<PacketEvent>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.last.is_some() {
+                thread::sleep(self.interval);
+            }
+            if let Err(e) = self.poll() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    pub fn read_packet_status(&mut self) -> io::Result<PacketStatus> {
+        Ok(PacketStatus(
+            self.read_raw_status_reg(true, StatusRegAddr::PKTSTATUS)?.1,
+        ))
+    }
+    /// Poll `PKTSTATUS` every `interval` and yield edges. The first poll only records the
+    /// initial state.
+    pub fn packet_events(&mut self, interval: Duration) -> PacketEvents<'_, 'a, P> {
+        PacketEvents {
+            cc1101: self,
+            interval,
+            last: None,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_edges() {
+        let idle = PacketStatus(0x10);
+        let sync = PacketStatus(0x68);
+        assert_eq!(
+            sync.events_since(idle).collect::<Vec<_>>(),
+            [
+                PacketEvent::CarrierSensed,
+                PacketEvent::ChannelBusy,
+                PacketEvent::PreambleQualityReached,
+                PacketEvent::SyncDetected
+            ]
+        );
+        assert_eq!(
+            PacketStatus(0xe0).events_since(sync).collect::<Vec<_>>(),
+            [PacketEvent::PacketDone { crc_ok: true }]
+        );
+        assert_eq!(idle.events_since(idle).count(), 0);
+    }
+}
@@ -0,0 +1,273 @@
+//! Register level CC1101 model answering the endpoint protocol, for driver tests
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{self, Read, Write},
+    rc::Rc,
+};
+
+use strum::VariantArray;
+
+use super::{ChipState, CommandStrobe, ConfigRegAddr, MarcState, StatusRegAddr};
+
+pub(super) struct FakeChip {
+    pub config: [u8; ConfigRegAddr::ALL.len()],
+    pub pa_table: [u8; 8],
+    pub state: ChipState,
+    /// `CHIP_RDYn` stays high for this many more transfers
+    pub not_ready: usize,
+    /// Writes to these registers are dropped
+    pub stuck: Vec<ConfigRegAddr>,
+    /// `STX` in RX does nothing, as if CCA reported a busy channel
+    pub busy: bool,
+    /// `MARCSTATE` reads until a transmission ends in the `MCSM1.TXOFF_MODE` state
+    pub tx_reads: usize,
+    pub rx_fifo: VecDeque<u8>,
+    pub strobes: Vec<CommandStrobe>,
+    /// Single and burst config register reads
+    pub reads: Vec<ConfigRegAddr>,
+    pub writes: Vec<(ConfigRegAddr, u8)>,
+    input: Vec<u8>,
+    output: VecDeque<u8>,
+    tx_left: usize,
+}
+impl Default for FakeChip {
+    fn default() -> Self {
+        Self {
+            config: ConfigRegAddr::ALL.map(ConfigRegAddr::reset_value),
+            pa_table: Self::PA_TABLE_RESET,
+            state: ChipState::Idle,
+            not_ready: 0,
+            stuck: Vec::new(),
+            busy: false,
+            tx_reads: 0,
+            rx_fifo: VecDeque::new(),
+            strobes: Vec::new(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            input: Vec::new(),
+            output: VecDeque::new(),
+            tx_left: 0,
+        }
+    }
+}
+impl FakeChip {
+    const PA_TABLE_RESET: [u8; 8] = [0xc6, 0, 0, 0, 0, 0, 0, 0];
+
+    pub fn config_reg(&self, addr: ConfigRegAddr) -> u8 {
+        self.config[addr as usize]
+    }
+
+    fn status(&mut self) -> u8 {
+        let ready = if self.not_ready > 0 {
+            self.not_ready -= 1;
+            0x80
+        } else {
+            0
+        };
+        ready | (self.state as u8) << 4 | self.rx_fifo.len().min(15) as u8
+    }
+    fn marc_state(&mut self) -> MarcState {
+        if self.state == ChipState::Tx {
+            if self.tx_left == 0 {
+                self.state = match self.config_reg(ConfigRegAddr::MCSM1) & 0x03 {
+                    0 => ChipState::Idle,
+                    1 => ChipState::Fstxon,
+                    2 => ChipState::Tx,
+                    _ => ChipState::Rx,
+                };
+            } else {
+                self.tx_left -= 1;
+            }
+        }
+        match self.state {
+            ChipState::Idle => MarcState::Idle,
+            ChipState::Rx => MarcState::Rx,
+            ChipState::Tx => MarcState::Tx,
+            ChipState::Fstxon => MarcState::Fstxon,
+            ChipState::Calibrate => MarcState::Startcal,
+            ChipState::Settling => MarcState::FsLock,
+            ChipState::RxfifoOverflow => MarcState::RxfifoOverflow,
+            ChipState::TxfifoUnderflow => MarcState::TxfifoUnderflow,
+        }
+    }
+    fn status_reg(&mut self, addr: u8) -> u8 {
+        match addr {
+            a if a == StatusRegAddr::PARTNUM as u8 => 0x00,
+            a if a == StatusRegAddr::VERSION as u8 => 0x14,
+            a if a == StatusRegAddr::MARCSTATE as u8 => self.marc_state() as u8,
+            a if a == StatusRegAddr::RXBYTES as u8 => {
+                let overflow = self.state == ChipState::RxfifoOverflow;
+                self.rx_fifo.len().min(0x7f) as u8 | (overflow as u8) << 7
+            }
+            _ => 0,
+        }
+    }
+    fn strobe(&mut self, cmd: CommandStrobe) {
+        self.strobes.push(cmd);
+        match cmd {
+            CommandStrobe::SRES => *self = Self::default(),
+            CommandStrobe::SIDLE => self.state = ChipState::Idle,
+            CommandStrobe::SRX => self.state = ChipState::Rx,
+            CommandStrobe::STX if self.state == ChipState::Rx && self.busy => {}
+            CommandStrobe::STX => {
+                self.state = ChipState::Tx;
+                self.tx_left = self.tx_reads;
+            }
+            CommandStrobe::SFRX => self.rx_fifo.clear(),
+            // registers lost in SLEEP, the crystal takes a few polls to start again
+            CommandStrobe::SPWD => {
+                for addr in [
+                    ConfigRegAddr::TEST2,
+                    ConfigRegAddr::TEST1,
+                    ConfigRegAddr::TEST0,
+                ] {
+                    self.config[addr as usize] = addr.reset_value();
+                }
+                self.pa_table = Self::PA_TABLE_RESET;
+                self.not_ready = 3;
+            }
+            CommandStrobe::SXOFF => self.not_ready = 3,
+            _ => {}
+        }
+    }
+    fn write_config(&mut self, addr: usize, value: u8) {
+        let Some(&addr) = ConfigRegAddr::ALL.get(addr) else {
+            return;
+        };
+        self.writes.push((addr, value));
+        if !self.stuck.contains(&addr) {
+            self.config[addr as usize] = value & addr.writable_mask();
+        }
+    }
+    fn read_config(&mut self, addr: usize) -> u8 {
+        match ConfigRegAddr::ALL.get(addr) {
+            Some(&addr) => {
+                self.reads.push(addr);
+                self.config_reg(addr)
+            }
+            None => 0,
+        }
+    }
+
+    /// Answer the first complete transfer in `input`, returns false if there is none
+    fn transfer(&mut self) -> bool {
+        let input = std::mem::take(&mut self.input);
+        let used = match input[..] {
+            [] => 0,
+            // endpoint exit
+            [0x00, ..] => 1,
+            // bursts of 64 also start with 0xc0, but always have bit 6 of the address set
+            [0x80 | 0xc0, cmd, ref rest @ ..]
+                if cmd & 0x40 == 0 || (0x30..=0x3d).contains(&(cmd & 0x3f)) =>
+            {
+                let (addr, read) = (cmd & 0x3f, cmd & 0x80 != 0);
+                let status = self.status();
+                match addr {
+                    0x30..=0x3d if cmd & 0x40 != 0 => {
+                        let v = self.status_reg(addr);
+                        self.output.extend([status, v]);
+                        2
+                    }
+                    0x30..=0x3d => {
+                        if let Some(&cmd) =
+                            CommandStrobe::VARIANTS.iter().find(|c| **c as u8 == addr)
+                        {
+                            self.strobe(cmd);
+                        }
+                        self.output.push_back(status);
+                        2
+                    }
+                    _ if read => {
+                        let v = match addr {
+                            0x3f => self.rx_fifo.pop_front().unwrap_or(0),
+                            _ => self.read_config(addr as usize),
+                        };
+                        self.output.extend([status, v]);
+                        2
+                    }
+                    _ => match rest {
+                        [v, ..] => {
+                            if addr != 0x3f {
+                                self.write_config(addr as usize, *v);
+                            }
+                            self.output.extend([status, status]);
+                            3
+                        }
+                        [] => 0,
+                    },
+                }
+            }
+            [len, cmd, ref rest @ ..] => {
+                let (count, addr) = ((len & 0x7f) as usize, (cmd & 0x3f) as usize);
+                if cmd & 0x80 != 0 {
+                    let status = self.status();
+                    self.output.push_back(status);
+                    for idx in 0..count {
+                        let v = match addr {
+                            0x3e => self.pa_table[idx % 8],
+                            0x3f => self.rx_fifo.pop_front().unwrap_or(0),
+                            _ => self.read_config(addr + idx),
+                        };
+                        self.output.push_back(v);
+                    }
+                    2
+                } else if rest.len() >= count {
+                    let status = self.status();
+                    self.output.push_back(status);
+                    for (idx, v) in rest[..count].iter().enumerate() {
+                        match addr {
+                            0x3e => self.pa_table[idx % 8] = *v,
+                            0x3f => {}
+                            _ => self.write_config(addr + idx, *v),
+                        }
+                        self.output.push_back(status);
+                    }
+                    2 + count
+                } else {
+                    0
+                }
+            }
+            [_] => 0,
+        };
+        self.input = input[used..].to_vec();
+        used != 0
+    }
+}
+
+/// Shared handle so tests can inspect and change the chip while a driver borrows the port
+#[derive(Clone, Default)]
+pub(super) struct FakePort(pub Rc<RefCell<FakeChip>>);
+impl FakePort {
+    pub fn chip(&self) -> std::cell::RefMut<'_, FakeChip> {
+        self.0.borrow_mut()
+    }
+}
+impl Read for FakePort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut chip = self.chip();
+        let len = buf.len().min(chip.output.len());
+        for (dst, src) in buf.iter_mut().zip(chip.output.drain(..len)) {
+            *dst = src;
+        }
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "read without pending response",
+            ));
+        }
+        Ok(len)
+    }
+}
+impl Write for FakePort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut chip = self.chip();
+        chip.input.extend(buf);
+        while chip.transfer() {}
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
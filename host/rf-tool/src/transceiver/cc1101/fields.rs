@@ -0,0 +1,419 @@
+//! Bit fields of config registers, from the datasheet register descriptions
+
+use super::{ConfigRegAddr, GdoCfg};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldValues {
+    Number,
+    /// Single bit, only shown when set
+    Flag,
+    /// Meaning of each value
+    Names(&'static [&'static str]),
+    /// `GDOx_CFG`
+    Gdo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegField {
+    pub name: &'static str,
+    /// Lowest bit
+    pub shift: u8,
+    pub width: u8,
+    pub values: FieldValues,
+}
+impl RegField {
+    pub const fn mask(&self) -> u8 {
+        (((1u16 << self.width) - 1) << self.shift) as u8
+    }
+    pub const fn get(&self, reg: u8) -> u8 {
+        (reg & self.mask()) >> self.shift
+    }
+    /// `NAME=value`, `None` for a flag that is not set
+    pub fn describe(&self, reg: u8) -> Option<String> {
+        let v = self.get(reg);
+        match self.values {
+            FieldValues::Number => Some(format!("{}={v}", self.name)),
+            FieldValues::Flag => (v != 0).then(|| self.name.to_string()),
+            FieldValues::Names(names) => Some(format!("{}={}", self.name, names[v as usize])),
+            FieldValues::Gdo => Some(match GdoCfg::try_from(v) {
+                Ok(cfg) => format!("{}={cfg:?}", self.name),
+                Err(v) => format!("{}=reserved({v:#04x})", self.name),
+            }),
+        }
+    }
+}
+
+const fn num(name: &'static str, hi: u8, lo: u8) -> RegField {
+    RegField {
+        name,
+        shift: lo,
+        width: hi - lo + 1,
+        values: FieldValues::Number,
+    }
+}
+const fn flag(name: &'static str, bit: u8) -> RegField {
+    RegField {
+        name,
+        shift: bit,
+        width: 1,
+        values: FieldValues::Flag,
+    }
+}
+const fn names(name: &'static str, hi: u8, lo: u8, names: &'static [&'static str]) -> RegField {
+    RegField {
+        name,
+        shift: lo,
+        width: hi - lo + 1,
+        values: FieldValues::Names(names),
+    }
+}
+const fn gdo(name: &'static str) -> RegField {
+    RegField {
+        name,
+        shift: 0,
+        width: 6,
+        values: FieldValues::Gdo,
+    }
+}
+
+const OFF_MODES: &[&str] = &["IDLE", "FSTXON", "TX", "RX"];
+
+impl ConfigRegAddr {
+    /// Fields from most to least significant bit, unused bits are omitted
+    pub const fn fields(self) -> &'static [RegField] {
+        match self {
+            Self::IOCFG2 => const { &[flag("GDO2_INV", 6), gdo("GDO2_CFG")] },
+            Self::IOCFG1 => const { &[flag("GDO_DS", 7), flag("GDO1_INV", 6), gdo("GDO1_CFG")] },
+            Self::IOCFG0 => {
+                const {
+                    &[
+                        flag("TEMP_SENSOR_ENABLE", 7),
+                        flag("GDO0_INV", 6),
+                        gdo("GDO0_CFG"),
+                    ]
+                }
+            }
+            Self::FIFOTHR => {
+                const {
+                    &[
+                        flag("ADC_RETENTION", 6),
+                        names("CLOSE_IN_RX", 5, 4, &["0dB", "6dB", "12dB", "18dB"]),
+                        num("FIFO_THR", 3, 0),
+                    ]
+                }
+            }
+            Self::SYNC1 => const { &[num("SYNC[15:8]", 7, 0)] },
+            Self::SYNC0 => const { &[num("SYNC[7:0]", 7, 0)] },
+            Self::PKTLEN => const { &[num("PACKET_LENGTH", 7, 0)] },
+            Self::PKTCTRL1 => {
+                const {
+                    &[
+                        num("PQT", 7, 5),
+                        flag("CRC_AUTOFLUSH", 3),
+                        flag("APPEND_STATUS", 2),
+                        names(
+                            "ADR_CHK",
+                            1,
+                            0,
+                            &["none", "addr", "addr+0x00", "addr+0x00+0xff"],
+                        ),
+                    ]
+                }
+            }
+            Self::PKTCTRL0 => {
+                const {
+                    &[
+                        flag("WHITE_DATA", 6),
+                        names(
+                            "PKT_FORMAT",
+                            5,
+                            4,
+                            &[
+                                "normal",
+                                "synchronous serial",
+                                "random TX",
+                                "asynchronous serial",
+                            ],
+                        ),
+                        flag("CRC_EN", 2),
+                        names(
+                            "LENGTH_CONFIG",
+                            1,
+                            0,
+                            &["fixed", "variable", "infinite", "reserved"],
+                        ),
+                    ]
+                }
+            }
+            Self::ADDR => const { &[num("DEVICE_ADDR", 7, 0)] },
+            Self::CHANNR => const { &[num("CHAN", 7, 0)] },
+            Self::FSCTRL1 => const { &[num("FREQ_IF", 4, 0)] },
+            Self::FSCTRL0 => const { &[num("FREQOFF", 7, 0)] },
+            Self::FREQ2 => const { &[num("FREQ[21:16]", 5, 0)] },
+            Self::FREQ1 => const { &[num("FREQ[15:8]", 7, 0)] },
+            Self::FREQ0 => const { &[num("FREQ[7:0]", 7, 0)] },
+            Self::MDMCFG4 => {
+                const {
+                    &[
+                        num("CHANBW_E", 7, 6),
+                        num("CHANBW_M", 5, 4),
+                        num("DRATE_E", 3, 0),
+                    ]
+                }
+            }
+            Self::MDMCFG3 => const { &[num("DRATE_M", 7, 0)] },
+            Self::MDMCFG2 => {
+                const {
+                    &[
+                        flag("DEM_DCFILT_OFF", 7),
+                        names(
+                            "MOD_FORMAT",
+                            6,
+                            4,
+                            &[
+                                "2-FSK", "GFSK", "reserved", "ASK/OOK", "4-FSK", "reserved",
+                                "reserved", "MSK",
+                            ],
+                        ),
+                        flag("MANCHESTER_EN", 3),
+                        names(
+                            "SYNC_MODE",
+                            2,
+                            0,
+                            &[
+                                "none",
+                                "15/16",
+                                "16/16",
+                                "30/32",
+                                "carrier sense",
+                                "15/16+carrier sense",
+                                "16/16+carrier sense",
+                                "30/32+carrier sense",
+                            ],
+                        ),
+                    ]
+                }
+            }
+            Self::MDMCFG1 => {
+                const {
+                    &[
+                        flag("FEC_EN", 7),
+                        names(
+                            "NUM_PREAMBLE",
+                            6,
+                            4,
+                            &["2", "3", "4", "6", "8", "12", "16", "24"],
+                        ),
+                        num("CHANSPC_E", 1, 0),
+                    ]
+                }
+            }
+            Self::MDMCFG0 => const { &[num("CHANSPC_M", 7, 0)] },
+            Self::DEVIATN => const { &[num("DEVIATION_E", 6, 4), num("DEVIATION_M", 2, 0)] },
+            Self::MCSM2 => {
+                const {
+                    &[
+                        flag("RX_TIME_RSSI", 4),
+                        flag("RX_TIME_QUAL", 3),
+                        num("RX_TIME", 2, 0),
+                    ]
+                }
+            }
+            Self::MCSM1 => {
+                const {
+                    &[
+                        names(
+                            "CCA_MODE",
+                            5,
+                            4,
+                            &[
+                                "always",
+                                "RSSI below threshold",
+                                "unless receiving",
+                                "RSSI below threshold unless receiving",
+                            ],
+                        ),
+                        names("RXOFF_MODE", 3, 2, OFF_MODES),
+                        names("TXOFF_MODE", 1, 0, OFF_MODES),
+                    ]
+                }
+            }
+            Self::MCSM0 => {
+                const {
+                    &[
+                        names(
+                            "FS_AUTOCAL",
+                            5,
+                            4,
+                            &["never", "from IDLE", "to IDLE", "every 4th to IDLE"],
+                        ),
+                        names("PO_TIMEOUT", 3, 2, &["1", "16", "64", "256"]),
+                        flag("PIN_CTRL_EN", 1),
+                        flag("XOSC_FORCE_ON", 0),
+                    ]
+                }
+            }
+            Self::FOCCFG => {
+                const {
+                    &[
+                        flag("FOC_BS_CS_GATE", 5),
+                        names("FOC_PRE_K", 4, 3, &["K", "2K", "3K", "4K"]),
+                        names("FOC_POST_K", 2, 2, &["FOC_PRE_K", "K/2"]),
+                        names("FOC_LIMIT", 1, 0, &["0", "BW/8", "BW/4", "BW/2"]),
+                    ]
+                }
+            }
+            Self::BSCFG => {
+                const {
+                    &[
+                        names("BS_PRE_KI", 7, 6, &["KI", "2KI", "3KI", "4KI"]),
+                        names("BS_PRE_KP", 5, 4, &["KP", "2KP", "3KP", "4KP"]),
+                        names("BS_POST_KI", 3, 3, &["BS_PRE_KI", "KI/2"]),
+                        names("BS_POST_KP", 2, 2, &["BS_PRE_KP", "KP"]),
+                        names("BS_LIMIT", 1, 0, &["0", "3.125%", "6.25%", "12.5%"]),
+                    ]
+                }
+            }
+            Self::AGCTRL2 => {
+                const {
+                    &[
+                        names(
+                            "MAX_DVGA_GAIN",
+                            7,
+                            6,
+                            &[
+                                "all",
+                                "all but highest",
+                                "all but 2 highest",
+                                "all but 3 highest",
+                            ],
+                        ),
+                        num("MAX_LNA_GAIN", 5, 3),
+                        names(
+                            "MAGN_TARGET",
+                            2,
+                            0,
+                            &[
+                                "24dB", "27dB", "30dB", "33dB", "36dB", "38dB", "40dB", "42dB",
+                            ],
+                        ),
+                    ]
+                }
+            }
+            Self::AGCTRL1 => {
+                const {
+                    &[
+                        flag("AGC_LNA_PRIORITY", 6),
+                        names(
+                            "CARRIER_SENSE_REL_THR",
+                            5,
+                            4,
+                            &["disabled", "6dB", "10dB", "14dB"],
+                        ),
+                        num("CARRIER_SENSE_ABS_THR", 3, 0),
+                    ]
+                }
+            }
+            Self::AGCTRL0 => {
+                const {
+                    &[
+                        names("HYST_LEVEL", 7, 6, &["none", "low", "medium", "high"]),
+                        names("WAIT_TIME", 5, 4, &["8", "16", "24", "32"]),
+                        names(
+                            "AGC_FREEZE",
+                            3,
+                            2,
+                            &["never", "after sync word", "analog", "analog and digital"],
+                        ),
+                        num("FILTER_LENGTH", 1, 0),
+                    ]
+                }
+            }
+            Self::WOREVT1 => const { &[num("EVENT0[15:8]", 7, 0)] },
+            Self::WOREVT0 => const { &[num("EVENT0[7:0]", 7, 0)] },
+            Self::WORCTRL => {
+                const {
+                    &[
+                        flag("RC_PD", 7),
+                        num("EVENT1", 6, 4),
+                        flag("RC_CAL", 3),
+                        num("WOR_RES", 1, 0),
+                    ]
+                }
+            }
+            Self::FREND1 => {
+                const {
+                    &[
+                        num("LNA_CURRENT", 7, 6),
+                        num("LNA2MIX_CURRENT", 5, 4),
+                        num("LODIV_BUF_CURRENT_RX", 3, 2),
+                        num("MIX_CURRENT", 1, 0),
+                    ]
+                }
+            }
+            Self::FREND0 => const { &[num("LODIV_BUF_CURRENT_TX", 5, 4), num("PA_POWER", 2, 0)] },
+            Self::FSCAL3 => {
+                const {
+                    &[
+                        num("FSCAL3[7:6]", 7, 6),
+                        num("CHP_CURR_CAL_EN", 5, 4),
+                        num("FSCAL3[3:0]", 3, 0),
+                    ]
+                }
+            }
+            Self::FSCAL2 => const { &[flag("VCO_CORE_H_EN", 5), num("FSCAL2", 4, 0)] },
+            Self::FSCAL1 => const { &[num("FSCAL1", 5, 0)] },
+            Self::FSCAL0 => const { &[num("FSCAL0", 6, 0)] },
+            Self::RCCTRL1 => const { &[num("RCCTRL1", 6, 0)] },
+            Self::RCCTRL0 => const { &[num("RCCTRL0", 6, 0)] },
+            Self::FSTEST => const { &[num("FSTEST", 7, 0)] },
+            Self::PTEST => const { &[num("PTEST", 7, 0)] },
+            Self::AGCTEST => const { &[num("AGCTEST", 7, 0)] },
+            Self::TEST2 => const { &[num("TEST2", 7, 0)] },
+            Self::TEST1 => const { &[num("TEST1", 7, 0)] },
+            Self::TEST0 => {
+                const {
+                    &[
+                        num("TEST0[7:2]", 7, 2),
+                        flag("VCO_SEL_CAL_EN", 1),
+                        num("TEST0[0]", 0, 0),
+                    ]
+                }
+            }
+        }
+    }
+
+    /// Fields of `value`, e.g. `MOD_FORMAT=ASK/OOK, SYNC_MODE=none`
+    pub fn describe(self, value: u8) -> String {
+        self.fields()
+            .iter()
+            .filter_map(|f| f.describe(value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_values() {
+        assert_eq!(
+            ConfigRegAddr::MDMCFG2.describe(0x30),
+            "MOD_FORMAT=ASK/OOK, SYNC_MODE=none"
+        );
+        assert_eq!(
+            ConfigRegAddr::IOCFG0.describe(ConfigRegAddr::IOCFG0.reset_value()),
+            "GDO0_CFG=ClkXosc192"
+        );
+        // fields don't overlap
+        for addr in ConfigRegAddr::ALL {
+            let mut seen = 0;
+            for field in addr.fields() {
+                assert_eq!(seen & field.mask(), 0, "{addr:?} {}", field.name);
+                seen |= field.mask();
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Carrier frequency from `FREQ2..FREQ0`, `CHANNR` and channel spacing
+
+use std::io::{self, Read, Write};
+
+use super::{Cc1101, ConfigRegAddr, Status};
+
+/// Crystal frequency of common CC1101 modules
+pub const XOSC_FREQ: u32 = 26_000_000;
+
+/// `FREQ` word for base frequency `freq` in Hz, `None` if it does not fit in 22 bits
+pub fn freq_word(xosc_freq: u32, freq: u64) -> Option<u32> {
+    let word = ((freq << 16) + xosc_freq as u64 / 2) / xosc_freq as u64;
+    (word < 1 << 22).then_some(word as u32)
+}
+
+/// Base frequency in Hz of `FREQ` word
+pub fn base_freq(xosc_freq: u32, word: u32) -> u64 {
+    (word as u64 * xosc_freq as u64) >> 16
+}
+
+/// Channel spacing in Hz from `MDMCFG1.CHANSPC_E` and `MDMCFG0.CHANSPC_M`
+pub fn channel_spacing(xosc_freq: u32, mdmcfg1: u8, mdmcfg0: u8) -> u64 {
+    ((256 + mdmcfg0 as u64) * xosc_freq as u64) << (mdmcfg1 & 0x3) >> 18
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    /// Write `FREQ2..FREQ0` for base frequency `freq` in Hz
+    pub fn set_base_frequency(&mut self, xosc_freq: u32, freq: u64) -> io::Result<Status> {
+        let word = freq_word(xosc_freq, freq)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frequency out of range"))?;
+        let [_, f2, f1, f0] = word.to_be_bytes();
+        Ok(self
+            .write_raw_config_burst(ConfigRegAddr::FREQ2, &[f2, f1, f0])?
+            .0)
+    }
+    /// Carrier frequency in Hz of the current channel
+    pub fn read_carrier_frequency(&mut self, xosc_freq: u32) -> io::Result<u64> {
+        let [f2, f1, f0] = self.read_raw_config_burst(ConfigRegAddr::FREQ2)?.1;
+        let [mdmcfg1, mdmcfg0] = self.read_raw_config_burst(ConfigRegAddr::MDMCFG1)?.1;
+        let channr = self.read_raw_config_reg(true, ConfigRegAddr::CHANNR)?.1;
+        Ok(base_freq(xosc_freq, u32::from_be_bytes([0, f2, f1, f0]))
+            + channr as u64 * channel_spacing(xosc_freq, mdmcfg1, mdmcfg0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_defaults() {
+        // FREQ=0x1EC4EC, CHANSPC_E=2, CHANSPC_M=0xF8 after reset
+        assert_eq!(base_freq(XOSC_FREQ, 0x1EC4EC), 799_999_877);
+        assert_eq!(channel_spacing(XOSC_FREQ, 0x22, 0xF8), 199_951);
+        assert_eq!(freq_word(XOSC_FREQ, 433_920_000), Some(0x10B071));
+    }
+}
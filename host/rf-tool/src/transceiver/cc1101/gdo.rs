@@ -0,0 +1,121 @@
+//! Typed `IOCFGx` configuration
+
+use std::io::{self, Read, Write};
+
+use super::{Cc1101, ConfigRegAddr, GdoCfg, Status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdoPin {
+    Gdo0,
+    Gdo1,
+    Gdo2,
+}
+impl GdoPin {
+    pub const fn reg(self) -> ConfigRegAddr {
+        match self {
+            Self::Gdo0 => ConfigRegAddr::IOCFG0,
+            Self::Gdo1 => ConfigRegAddr::IOCFG1,
+            Self::Gdo2 => ConfigRegAddr::IOCFG2,
+        }
+    }
+}
+
+/// Value of `IOCFGx`. Bit 7 is `TEMP_SENSOR_ENABLE` in `IOCFG0`, `GDO_DS` in `IOCFG1` and
+/// unused in `IOCFG2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GdoPinConfig {
+    pub cfg: GdoCfg,
+    /// `GDOx_INV`, invert output
+    pub invert: bool,
+    /// `IOCFG1.GDO_DS`, high output drive strength on all pins
+    pub high_drive: bool,
+    /// `IOCFG0.TEMP_SENSOR_ENABLE`, analog temperature sensor output on `GDO0`
+    pub temp_sensor: bool,
+}
+impl GdoPinConfig {
+    pub const fn new(cfg: GdoCfg) -> Self {
+        Self {
+            cfg,
+            invert: false,
+            high_drive: false,
+            temp_sensor: false,
+        }
+    }
+    pub const fn inverted(self) -> Self {
+        Self {
+            invert: true,
+            ..self
+        }
+    }
+
+    /// Register value for `pin`, `None` if bit 7 is set for a pin without that field
+    pub const fn to_reg(self, pin: GdoPin) -> Option<u8> {
+        let bit7 = match pin {
+            GdoPin::Gdo0 if !self.high_drive => self.temp_sensor,
+            GdoPin::Gdo1 if !self.temp_sensor => self.high_drive,
+            GdoPin::Gdo2 if !self.high_drive && !self.temp_sensor => false,
+            _ => return None,
+        };
+        Some((bit7 as u8) << 7 | (self.invert as u8) << 6 | self.cfg as u8)
+    }
+    /// Parse `IOCFGx` of `pin`, `Err` with `GDOx_CFG` if the signal is reserved
+    pub fn from_reg(pin: GdoPin, value: u8) -> Result<Self, u8> {
+        let bit7 = value & 0x80 != 0;
+        Ok(Self {
+            cfg: GdoCfg::try_from(value & 0x3f)?,
+            invert: value & 0x40 != 0,
+            high_drive: bit7 && pin == GdoPin::Gdo1,
+            temp_sensor: bit7 && pin == GdoPin::Gdo0,
+        })
+    }
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    /// Write `IOCFGx` of `pin`. In asynchronous serial mode `GDO0` is the TX data input, and
+    /// [`GdoCfg::SerialDataOutput`] outputs RX data.
+    pub fn configure_gdo(&mut self, pin: GdoPin, cfg: GdoPinConfig) -> io::Result<Status> {
+        let value = cfg.to_reg(pin).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{pin:?} has no such field"),
+            )
+        })?;
+        Ok(self.write_raw_config_reg(true, pin.reg(), value)?.0)
+    }
+    pub fn read_gdo_config(&mut self, pin: GdoPin) -> io::Result<GdoPinConfig> {
+        let value = self.read_raw_config_reg(true, pin.reg())?.1;
+        GdoPinConfig::from_reg(pin, value).map_err(|cfg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("reserved GDOx_CFG {cfg:#04x}"),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_values() {
+        // reset values of IOCFG2..IOCFG0
+        for (pin, value) in [
+            (GdoPin::Gdo2, 0x29),
+            (GdoPin::Gdo1, 0x2E),
+            (GdoPin::Gdo0, 0x3F),
+        ] {
+            assert_eq!(value, pin.reg().reset_value());
+            let cfg = GdoPinConfig::from_reg(pin, value).unwrap();
+            assert_eq!(cfg.to_reg(pin), Some(value));
+        }
+
+        let data = GdoPinConfig {
+            high_drive: true,
+            ..GdoPinConfig::new(GdoCfg::SerialDataOutput).inverted()
+        };
+        assert_eq!(data.to_reg(GdoPin::Gdo1), Some(0xCD));
+        assert_eq!(data.to_reg(GdoPin::Gdo0), None);
+        assert_eq!(GdoPinConfig::from_reg(GdoPin::Gdo2, 0x10), Err(0x10));
+    }
+}
@@ -0,0 +1,555 @@
+//! Channel plans and regional limits on carrier frequency and output power
+
+use std::io::{self, Read, Write};
+
+use super::{
+    driver::{Missing, Shadow},
+    freq::{base_freq, channel_spacing, freq_word},
+    Cc1101, ConfigRegAddr, Status,
+};
+
+/// Evenly spaced channels starting at `base_freq`, all in Hz
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelPlan {
+    pub base_freq: u64,
+    pub spacing: u64,
+    /// Number of channels, at most 256 as `CHANNR` is 8 bits
+    pub channel_count: u16,
+}
+
+/// Register values of a [`ChannelPlan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelRegs {
+    /// `FREQ2..FREQ0`
+    pub freq: [u8; 3],
+    /// `MDMCFG1.CHANSPC_E`
+    pub chanspc_e: u8,
+    /// `MDMCFG0.CHANSPC_M`
+    pub chanspc_m: u8,
+}
+impl ChannelRegs {
+    pub fn base_freq(&self, xosc_freq: u32) -> u64 {
+        let [f2, f1, f0] = self.freq;
+        base_freq(xosc_freq, u32::from_be_bytes([0, f2, f1, f0]))
+    }
+    pub fn spacing(&self, xosc_freq: u32) -> u64 {
+        channel_spacing(xosc_freq, self.chanspc_e, self.chanspc_m)
+    }
+}
+
+/// `(CHANSPC_E, CHANSPC_M)` closest to `spacing` in Hz, `None` if out of range
+pub fn spacing_regs(xosc_freq: u32, spacing: u64) -> Option<(u8, u8)> {
+    (0..4u8)
+        .filter_map(|e| {
+            let div = (xosc_freq as u64) << e;
+            let m = ((spacing << 18) + div / 2) / div;
+            let m = u8::try_from(m.checked_sub(256)?).ok()?;
+            Some((e, m))
+        })
+        .min_by_key(|(e, m)| channel_spacing(xosc_freq, *e, *m).abs_diff(spacing))
+}
+
+impl ChannelPlan {
+    /// Nominal frequency of `channel`, `None` if not in plan
+    pub fn channel_freq(&self, channel: u8) -> Option<u64> {
+        ((channel as u16) < self.channel_count)
+            .then(|| self.base_freq + channel as u64 * self.spacing)
+    }
+    /// Register values, `None` if base frequency or spacing can't be represented
+    pub fn registers(&self, xosc_freq: u32) -> Option<ChannelRegs> {
+        let [_, f2, f1, f0] = freq_word(xosc_freq, self.base_freq)?.to_be_bytes();
+        let (chanspc_e, chanspc_m) = spacing_regs(xosc_freq, self.spacing)?;
+        Some(ChannelRegs {
+            freq: [f2, f1, f0],
+            chanspc_e,
+            chanspc_m,
+        })
+    }
+}
+
+/// Frequency range with its limits. Duty cycle is informational, the driver doesn't track
+/// time spent transmitting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Band {
+    /// Lowest allowed carrier frequency in Hz
+    pub start: u64,
+    /// Highest allowed carrier frequency in Hz
+    pub end: u64,
+    pub max_power_dbm: i8,
+    /// Fraction of time allowed to transmit, `None` if unrestricted
+    pub max_duty_cycle: Option<f32>,
+}
+impl Band {
+    pub fn contains(&self, freq: u64) -> bool {
+        (self.start..=self.end).contains(&freq)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub bands: &'static [Band],
+}
+impl Region {
+    pub const ALL: [&'static Region; 4] = [&ISM_315, &ISM_433, &ISM_868, &ISM_915];
+
+    pub fn by_name(name: &str) -> Option<&'static Region> {
+        Self::ALL
+            .into_iter()
+            .find(|r| r.name.eq_ignore_ascii_case(name))
+    }
+    /// Band containing `freq`
+    pub fn band(&self, freq: u64) -> Option<&'static Band> {
+        self.bands.iter().find(|b| b.contains(freq))
+    }
+}
+
+/// US periodic operation (FCC 15.231), roughly the peak field strength limit
+pub const ISM_315: Region = Region {
+    name: "ism315",
+    description: "315 MHz, FCC 15.231",
+    bands: &[Band {
+        start: 314_000_000,
+        end: 316_000_000,
+        max_power_dbm: 0,
+        max_duty_cycle: None,
+    }],
+};
+/// ERC 70-03 annex 1 band f
+pub const ISM_433: Region = Region {
+    name: "ism433",
+    description: "433 MHz, ERC 70-03",
+    bands: &[Band {
+        start: 433_050_000,
+        end: 434_790_000,
+        max_power_dbm: 10,
+        max_duty_cycle: Some(0.1),
+    }],
+};
+/// ERC 70-03 annex 1 bands g1.1 to g1.4
+pub const ISM_868: Region = Region {
+    name: "ism868",
+    description: "868 MHz, ERC 70-03",
+    bands: &[
+        Band {
+            start: 868_000_000,
+            end: 868_600_000,
+            max_power_dbm: 14,
+            max_duty_cycle: Some(0.01),
+        },
+        Band {
+            start: 868_700_000,
+            end: 869_200_000,
+            max_power_dbm: 14,
+            max_duty_cycle: Some(0.001),
+        },
+        Band {
+            start: 869_400_000,
+            end: 869_650_000,
+            max_power_dbm: 27,
+            max_duty_cycle: Some(0.1),
+        },
+        Band {
+            start: 869_700_000,
+            end: 870_000_000,
+            max_power_dbm: 7,
+            max_duty_cycle: None,
+        },
+    ],
+};
+/// US field disturbance band (FCC 15.249), roughly the field strength limit
+pub const ISM_915: Region = Region {
+    name: "ism915",
+    description: "915 MHz, FCC 15.249",
+    bands: &[Band {
+        start: 902_000_000,
+        end: 928_000_000,
+        max_power_dbm: -1,
+        max_duty_cycle: None,
+    }],
+};
+
+/// Optimum PATABLE settings from datasheet table 39, for 315/433/868/915 MHz
+const PA_POWER: [(u64, [(u8, i8); 8]); 4] = [
+    (
+        315_000_000,
+        [
+            (0x12, -30),
+            (0x0D, -20),
+            (0x1C, -15),
+            (0x34, -10),
+            (0x51, 0),
+            (0x85, 5),
+            (0xCB, 7),
+            (0xC2, 10),
+        ],
+    ),
+    (
+        433_000_000,
+        [
+            (0x12, -30),
+            (0x0E, -20),
+            (0x1D, -15),
+            (0x34, -10),
+            (0x60, 0),
+            (0x84, 5),
+            (0xC8, 7),
+            (0xC0, 10),
+        ],
+    ),
+    (
+        868_000_000,
+        [
+            (0x03, -30),
+            (0x0F, -20),
+            (0x1E, -15),
+            (0x27, -10),
+            (0x50, 0),
+            (0x81, 5),
+            (0xCB, 7),
+            (0xC2, 10),
+        ],
+    ),
+    (
+        915_000_000,
+        [
+            (0x03, -30),
+            (0x0E, -20),
+            (0x1E, -15),
+            (0x27, -10),
+            (0x8E, 0),
+            (0xCD, 5),
+            (0xC7, 7),
+            (0xC0, 10),
+        ],
+    ),
+];
+
+/// Output power in dBm of PATABLE entry `value` near carrier `freq`, `None` if not a
+/// documented setting. `0x00` turns the PA off and has no output power.
+pub fn pa_power_dbm(freq: u64, value: u8) -> Option<i8> {
+    let (_, table) = PA_POWER
+        .iter()
+        .min_by_key(|(f, _)| f.abs_diff(freq))
+        .unwrap();
+    table.iter().find(|(v, _)| *v == value).map(|(_, p)| *p)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionError {
+    FrequencyOutOfRegion {
+        freq: u64,
+        region: &'static str,
+    },
+    PowerTooHigh {
+        value: u8,
+        power_dbm: i8,
+        max_power_dbm: i8,
+    },
+    /// PATABLE value not in datasheet table, so its power can't be checked
+    UnknownPower {
+        value: u8,
+    },
+}
+impl std::fmt::Display for RegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FrequencyOutOfRegion { freq, region } => {
+                write!(f, "{freq} Hz is outside region {region}")
+            }
+            Self::PowerTooHigh {
+                value,
+                power_dbm,
+                max_power_dbm,
+            } => write!(
+                f,
+                "PATABLE value {value:#04x} gives {power_dbm} dBm, more than {max_power_dbm} dBm allowed"
+            ),
+            Self::UnknownPower { value } => {
+                write!(f, "output power of PATABLE value {value:#04x} is unknown")
+            }
+        }
+    }
+}
+impl std::error::Error for RegionError {}
+impl From<RegionError> for io::Error {
+    fn from(value: RegionError) -> Self {
+        io::Error::new(io::ErrorKind::PermissionDenied, value)
+    }
+}
+
+impl Region {
+    pub fn check_frequency(&self, freq: u64) -> Result<&'static Band, RegionError> {
+        self.band(freq).ok_or(RegionError::FrequencyOutOfRegion {
+            freq,
+            region: self.name,
+        })
+    }
+    /// Check every channel of `plan` as realized by `regs`
+    pub fn check_channel_plan(
+        &self,
+        xosc_freq: u32,
+        plan: &ChannelPlan,
+        regs: &ChannelRegs,
+    ) -> Result<(), RegionError> {
+        let (base, spacing) = (regs.base_freq(xosc_freq), regs.spacing(xosc_freq));
+        for ch in 0..plan.channel_count as u64 {
+            self.check_frequency(base + ch * spacing)?;
+        }
+        Ok(())
+    }
+    pub fn check_pa_table(&self, freq: u64, pa_table: &[u8; 8]) -> Result<(), RegionError> {
+        let band = self.check_frequency(freq)?;
+        for value in pa_table.iter().copied().filter(|v| *v != 0) {
+            let power_dbm = pa_power_dbm(freq, value).ok_or(RegionError::UnknownPower { value })?;
+            if power_dbm > band.max_power_dbm {
+                return Err(RegionError::PowerTooHigh {
+                    value,
+                    power_dbm,
+                    max_power_dbm: band.max_power_dbm,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Limits enforced by [`Cc1101`] on writes to frequency registers and PATABLE
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Regulation {
+    pub region: &'static Region,
+    pub xosc_freq: u32,
+    /// Allow writes outside `region`
+    pub override_limits: bool,
+}
+impl Regulation {
+    /// `regulation` unless its limits are overridden
+    pub(super) fn enforced(regulation: Option<Self>) -> Option<Self> {
+        regulation.filter(|r| !r.override_limits)
+    }
+    pub fn new(region: &'static Region, xosc_freq: u32) -> Self {
+        Self {
+            region,
+            xosc_freq,
+            override_limits: false,
+        }
+    }
+    /// Check writing `data` at `start_addr`, other registers from `shadow`. A new carrier
+    /// frequency is checked with the loaded PATABLE as well. Writing frequency registers one
+    /// by one may pass through frequencies outside the region, use burst writes instead.
+    pub(super) fn check_config_write(
+        &self,
+        shadow: &Shadow,
+        start_addr: ConfigRegAddr,
+        data: &[u8],
+    ) -> Result<Result<(), RegionError>, Missing> {
+        if !writes_freq_regs(start_addr, data.len()) {
+            return Ok(Ok(()));
+        }
+        let freq = shadow.carrier_after_write(self.xosc_freq, start_addr, data)?;
+        let pa_table = shadow.pa_table()?;
+        Ok(self.region.check_pa_table(freq, &pa_table))
+    }
+    /// Check writing `pa_table` at the carrier frequency in `shadow`
+    pub(super) fn check_pa_table_write(
+        &self,
+        shadow: &Shadow,
+        pa_table: &[u8; 8],
+    ) -> Result<Result<(), RegionError>, Missing> {
+        let freq = shadow.carrier_after_write(self.xosc_freq, ConfigRegAddr::IOCFG2, &[])?;
+        Ok(self.region.check_pa_table(freq, pa_table))
+    }
+}
+
+/// Registers the carrier frequency depends on
+pub(super) const FREQ_REGS: [ConfigRegAddr; 6] = [
+    ConfigRegAddr::CHANNR,
+    ConfigRegAddr::FREQ2,
+    ConfigRegAddr::FREQ1,
+    ConfigRegAddr::FREQ0,
+    ConfigRegAddr::MDMCFG1,
+    ConfigRegAddr::MDMCFG0,
+];
+
+/// Writing `len` registers at `start_addr` may change the carrier frequency
+pub(super) fn writes_freq_regs(start_addr: ConfigRegAddr, len: usize) -> bool {
+    let range = start_addr as usize..start_addr as usize + len;
+    FREQ_REGS.iter().any(|a| range.contains(&(*a as usize)))
+}
+/// Carrier frequency from values of [`FREQ_REGS`]
+pub(super) fn carrier_freq(xosc_freq: u32, values: [u8; FREQ_REGS.len()]) -> u64 {
+    let [channr, f2, f1, f0, mdmcfg1, mdmcfg0] = values;
+    base_freq(xosc_freq, u32::from_be_bytes([0, f2, f1, f0]))
+        + channr as u64 * channel_spacing(xosc_freq, mdmcfg1, mdmcfg0)
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    /// Refuse frequency and PATABLE writes outside a region, `None` to disable checks
+    pub fn set_regulation(&mut self, regulation: Option<Regulation>) {
+        self.regulation = regulation;
+    }
+    pub fn regulation(&self) -> Option<Regulation> {
+        self.regulation
+    }
+    /// Check a config write against the active [`Regulation`]
+    pub(super) fn check_config_write(
+        &mut self,
+        start_addr: ConfigRegAddr,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let Some(regulation) = Regulation::enforced(self.regulation) else {
+            return Ok(());
+        };
+        Ok(self.with_shadow(|shadow| regulation.check_config_write(shadow, start_addr, data))??)
+    }
+    pub(super) fn check_pa_table_write(&mut self, data: &[u8; 8]) -> io::Result<()> {
+        let Some(regulation) = Regulation::enforced(self.regulation) else {
+            return Ok(());
+        };
+        Ok(self.with_shadow(|shadow| regulation.check_pa_table_write(shadow, data))??)
+    }
+
+    /// Write base frequency and channel spacing of `plan`, and select channel 0
+    pub fn set_channel_plan(&mut self, xosc_freq: u32, plan: &ChannelPlan) -> io::Result<Status> {
+        let regs = plan.registers(xosc_freq).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "channel plan out of range")
+        })?;
+        if let Some(regulation) = Regulation::enforced(self.regulation) {
+            regulation
+                .region
+                .check_channel_plan(xosc_freq, plan, &regs)?;
+        }
+        // the whole plan is checked, so intermediate states don't matter
+        self.write_config_burst(ConfigRegAddr::CHANNR, &[0])?;
+        self.write_config_burst(ConfigRegAddr::FREQ2, &regs.freq)?;
+        let mdmcfg1 = self.config_reg_value(ConfigRegAddr::MDMCFG1)? & !0x03 | regs.chanspc_e;
+        Ok(self
+            .write_config_burst(ConfigRegAddr::MDMCFG1, &[mdmcfg1, regs.chanspc_m])?
+            .0)
+    }
+    /// Select `channel` of `plan`
+    pub fn set_channel(&mut self, plan: &ChannelPlan, channel: u8) -> io::Result<Status> {
+        if plan.channel_freq(channel).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "channel not in plan",
+            ));
+        }
+        Ok(self
+            .write_raw_config_reg(true, ConfigRegAddr::CHANNR, channel)?
+            .0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::freq::XOSC_FREQ, *};
+
+    #[test]
+    fn regulation_checks() {
+        static REGION: Region = ISM_433;
+        let regulation = Regulation::new(&REGION, XOSC_FREQ);
+        let mut shadow = Shadow::default();
+        let freq = [0x10, 0xb0, 0x71];
+        assert_eq!(
+            regulation.check_config_write(&shadow, ConfigRegAddr::FREQ2, &freq),
+            Err(Missing::Config(ConfigRegAddr::CHANNR))
+        );
+        assert_eq!(
+            regulation.check_config_write(&shadow, ConfigRegAddr::MDMCFG2, &[0x30]),
+            Ok(Ok(()))
+        );
+        shadow.update_config(ConfigRegAddr::CHANNR, &[0]);
+        shadow.update_config(ConfigRegAddr::MDMCFG1, &[0x22, 0xf8]);
+        assert_eq!(
+            regulation.check_config_write(&shadow, ConfigRegAddr::FREQ2, &freq),
+            Err(Missing::PaTable)
+        );
+        shadow.pa_table = Some([0, 0xc0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            regulation.check_config_write(&shadow, ConfigRegAddr::FREQ2, &freq),
+            Ok(Ok(()))
+        );
+        assert!(matches!(
+            regulation.check_config_write(&shadow, ConfigRegAddr::FREQ2, &[0x21, 0x62, 0x76]),
+            Ok(Err(RegionError::FrequencyOutOfRegion { .. }))
+        ));
+        shadow.update_config(ConfigRegAddr::FREQ2, &freq);
+        assert_eq!(
+            regulation.check_pa_table_write(&shadow, &[0, 0xc0, 0, 0, 0, 0, 0, 0]),
+            Ok(Ok(()))
+        );
+    }
+
+    #[test]
+    fn channel_plan_registers() {
+        let plan = ChannelPlan {
+            base_freq: 433_100_000,
+            spacing: 200_000,
+            channel_count: 8,
+        };
+        let regs = plan.registers(XOSC_FREQ).unwrap();
+        assert_eq!(regs.freq, [0x10, 0xA8, 0x5F]);
+        assert_eq!((regs.chanspc_e, regs.chanspc_m), (2, 0xF8));
+        assert_eq!(plan.channel_freq(8), None);
+        assert_eq!(ISM_433.check_channel_plan(XOSC_FREQ, &plan, &regs), Ok(()));
+
+        let wide = ChannelPlan {
+            channel_count: 16,
+            ..plan
+        };
+        assert!(matches!(
+            ISM_433.check_channel_plan(XOSC_FREQ, &wide, &regs),
+            Err(RegionError::FrequencyOutOfRegion { .. })
+        ));
+        assert_eq!(spacing_regs(XOSC_FREQ, 10_000), None);
+    }
+
+    #[test]
+    fn pa_table_limits() {
+        let freq = 433_920_000;
+        assert_eq!(
+            ISM_433.check_pa_table(freq, &[0, 0xC0, 0, 0, 0, 0, 0, 0]),
+            Ok(())
+        );
+        assert_eq!(
+            ISM_315.check_pa_table(315_000_000, &[0xC2; 8]),
+            Err(RegionError::PowerTooHigh {
+                value: 0xC2,
+                power_dbm: 10,
+                max_power_dbm: 0
+            })
+        );
+        assert_eq!(
+            ISM_433.check_pa_table(freq, &[0xFF; 8]),
+            Err(RegionError::UnknownPower { value: 0xFF })
+        );
+        assert!(ISM_868.check_pa_table(freq, &[0; 8]).is_err());
+        assert_eq!(ISM_868.band(869_500_000).unwrap().max_power_dbm, 27);
+    }
+
+    #[test]
+    fn pa_table_limits_after_retune() {
+        static REGION: Region = ISM_868;
+        let regulation = Regulation::new(&REGION, XOSC_FREQ);
+        let mut shadow = Shadow::default();
+        shadow.update_config(ConfigRegAddr::CHANNR, &[0]);
+        shadow.update_config(ConfigRegAddr::FREQ2, &[0x21, 0x71, 0x3b]);
+        shadow.update_config(ConfigRegAddr::MDMCFG1, &[0x22, 0xf8]);
+        let pa_table = [0, 0xC2, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            regulation.check_pa_table_write(&shadow, &pa_table),
+            Ok(Ok(()))
+        );
+        shadow.pa_table = Some(pa_table);
+        assert_eq!(
+            regulation.check_config_write(&shadow, ConfigRegAddr::FREQ2, &[0x21, 0x74, 0x2f]),
+            Ok(Err(RegionError::PowerTooHigh {
+                value: 0xC2,
+                power_dbm: 10,
+                max_power_dbm: 7
+            }))
+        );
+    }
+}
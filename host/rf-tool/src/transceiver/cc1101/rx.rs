@@ -0,0 +1,319 @@
+//! Continuous reception with RX FIFO overflow recovery
+
+use std::{
+    io::{self, Read, Write},
+    ops::ControlFlow,
+    thread,
+    time::Duration,
+};
+
+use super::{Cc1101, ChipState, CommandStrobe, ReadWrite, Status, StatusRegAddr};
+
+/// Typical RSSI offset in dB, see datasheet section 17.3
+pub const RSSI_OFFSET: f32 = 74.0;
+
+/// Convert raw `RSSI` value to dBm
+pub fn rssi_dbm(raw: u8) -> f32 {
+    (raw as i8) as f32 / 2.0 - RSSI_OFFSET
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxMode {
+    /// Variable length packets (`PKTCTRL0.LENGTH_CONFIG=1`), with RSSI and LQI/CRC_OK appended
+    /// if `PKTCTRL1.APPEND_STATUS=1`
+    Packet { append_status: bool },
+    /// Yield bytes as they arrive
+    Raw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    /// Payload, without length byte
+    pub data: Vec<u8>,
+    /// Appended `RSSI` and `LQI | CRC_OK << 7`
+    pub status: Option<[u8; 2]>,
+}
+impl Packet {
+    pub fn rssi_dbm(&self) -> Option<f32> {
+        self.status.map(|s| rssi_dbm(s[0]))
+    }
+    pub fn lqi(&self) -> Option<u8> {
+        self.status.map(|s| s[1] & 0x7f)
+    }
+    pub fn crc_ok(&self) -> Option<bool> {
+        self.status.map(|s| s[1] & 0x80 != 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RxItem {
+    Packet(Packet),
+    Chunk(Vec<u8>),
+}
+
+/// Blocking iterator over received data, created by [`Cc1101::receiver`]
+pub struct Receiver<'c, 'a, P> {
+    cc1101: &'c mut Cc1101<'a, P>,
+    mode: RxMode,
+    interval: Duration,
+    started: bool,
+    drops: u64,
+    /// Expected length including appended status, and bytes read so far
+    partial: Option<(usize, Vec<u8>)>,
+}
+impl<'c, 'a, P: Read + Write> Receiver<'c, 'a, P> {
+    /// Number of RX FIFO overflows recovered from
+    pub fn drops(&self) -> u64 {
+        self.drops
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        self.partial = None;
+        self.cc1101
+            .command_strobe(true, ReadWrite::Write, CommandStrobe::SIDLE)?;
+        self.cc1101
+            .command_strobe(true, ReadWrite::Write, CommandStrobe::SFRX)?;
+        self.cc1101
+            .command_strobe(true, ReadWrite::Write, CommandStrobe::SRX)?;
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, count: usize) -> io::Result<Vec<u8>> {
+        let mut ret = vec![0; count];
+        for chunk in ret.chunks_mut(64) {
+            self.cc1101.read_fifo_into(chunk)?;
+        }
+        Ok(ret)
+    }
+
+    fn poll(&mut self) -> io::Result<Option<RxItem>> {
+        if !self.started {
+            self.restart()?;
+            self.started = true;
+        }
+
+        let (status, rx_bytes) = self.cc1101.read_rx_bytes()?;
+        if rx_bytes & 0x80 != 0 || status.state() == ChipState::RxfifoOverflow {
+            self.drops += 1;
+            self.restart()?;
+            return Ok(None);
+        }
+        let mut available = (rx_bytes & 0x7f) as usize;
+        if available == 0 {
+            if status.state() == ChipState::Idle {
+                self.cc1101
+                    .command_strobe(true, ReadWrite::Write, CommandStrobe::SRX)?;
+            }
+            return Ok(None);
+        }
+
+        match self.mode {
+            RxMode::Raw => {
+                // the last byte must not be read while still receiving
+                let count = if status.state() == ChipState::Rx {
+                    available - 1
+                } else {
+                    available
+                };
+                Ok(if count == 0 {
+                    None
+                } else {
+                    Some(RxItem::Chunk(self.read_bytes(count)?))
+                })
+            }
+            RxMode::Packet { append_status } => {
+                let (total, mut buf) = match self.partial.take() {
+                    Some(v) => v,
+                    // the length byte is also the last one in the FIFO
+                    None if available < 2 && status.state() == ChipState::Rx => return Ok(None),
+                    None => {
+                        let len = self.cc1101.read_fifo(true)?.1 as usize;
+                        available -= 1;
+                        (
+                            len + if append_status { 2 } else { 0 },
+                            Vec::with_capacity(len + 2),
+                        )
+                    }
+                };
+                let remaining = total - buf.len();
+                let count = if available >= remaining {
+                    remaining
+                } else {
+                    available.saturating_sub(1)
+                };
+                if count != 0 {
+                    buf.extend(self.read_bytes(count)?);
+                }
+
+                if buf.len() < total {
+                    self.partial = Some((total, buf));
+                    return Ok(None);
+                }
+                let status = if append_status {
+                    let s = buf.split_off(buf.len() - 2);
+                    Some([s[0], s[1]])
+                } else {
+                    None
+                };
+                Ok(Some(RxItem::Packet(Packet { data: buf, status })))
+            }
+        }
+    }
+}
+impl<'c, 'a, P: Read + Write> Iterator for Receiver<'c, 'a, P> {
+    type Item = io::Result<RxItem>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.poll() {
+                Ok(Some(item)) => return Some(Ok(item)),
+                Ok(None) => thread::sleep(self.interval),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    /// Read `RXBYTES` until two consecutive reads agree, as suggested by errata
+    pub fn read_rx_bytes(&mut self) -> io::Result<(Status, u8)> {
+        let mut last = self.read_raw_status_reg(true, StatusRegAddr::RXBYTES)?.1;
+        loop {
+            let (status, v) = self.read_raw_status_reg(true, StatusRegAddr::RXBYTES)?;
+            if v == last {
+                return Ok((status, v));
+            }
+            last = v;
+        }
+    }
+
+    /// Flush RX FIFO and stay in RX, polling FIFO every `interval`. On RX FIFO overflow the
+    /// FIFO is flushed and RX is entered again.
+    pub fn receiver(&mut self, mode: RxMode, interval: Duration) -> Receiver<'_, 'a, P> {
+        Receiver {
+            cc1101: self,
+            mode,
+            interval,
+            started: false,
+            drops: 0,
+            partial: None,
+        }
+    }
+    /// Receive until `f` breaks, returns number of overflows
+    pub fn receive_with(
+        &mut self,
+        mode: RxMode,
+        interval: Duration,
+        mut f: impl FnMut(RxItem) -> ControlFlow<()>,
+    ) -> io::Result<u64> {
+        let mut receiver = self.receiver(mode, interval);
+        for item in receiver.by_ref() {
+            if f(item?).is_break() {
+                break;
+            }
+        }
+        Ok(receiver.drops())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::fake::FakePort, *};
+
+    #[test]
+    fn packet_across_polls() {
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        let mut cc1101 = Cc1101::new(&mut port);
+        let mut receiver = cc1101.receiver(
+            RxMode::Packet {
+                append_status: true,
+            },
+            Duration::ZERO,
+        );
+        assert_eq!(receiver.poll().unwrap(), None);
+        assert_eq!(
+            fake.chip().strobes,
+            [
+                CommandStrobe::SIDLE,
+                CommandStrobe::SFRX,
+                CommandStrobe::SRX
+            ]
+        );
+
+        fake.chip().rx_fifo.push_back(3);
+        assert_eq!(receiver.poll().unwrap(), None);
+        assert_eq!(fake.chip().rx_fifo.len(), 1);
+
+        fake.chip().rx_fifo.extend([0xa0, 0xa1]);
+        assert_eq!(receiver.poll().unwrap(), None);
+        // one byte is left while receiving
+        assert_eq!(fake.chip().rx_fifo, [0xa1]);
+        assert_eq!(receiver.partial, Some((5, vec![0xa0])));
+
+        fake.chip().rx_fifo.extend([0xa2, 0x2c, 0x81]);
+        assert_eq!(
+            receiver.poll().unwrap(),
+            Some(RxItem::Packet(Packet {
+                data: vec![0xa0, 0xa1, 0xa2],
+                status: Some([0x2c, 0x81]),
+            }))
+        );
+        assert!(fake.chip().rx_fifo.is_empty());
+
+        // out of RX the length byte can be read alone
+        fake.chip().state = ChipState::Idle;
+        fake.chip().rx_fifo.push_back(1);
+        assert_eq!(receiver.poll().unwrap(), None);
+        assert_eq!(receiver.partial, Some((3, vec![])));
+    }
+
+    #[test]
+    fn overflow_restarts() {
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        let mut cc1101 = Cc1101::new(&mut port);
+        let mut receiver = cc1101.receiver(
+            RxMode::Packet {
+                append_status: false,
+            },
+            Duration::ZERO,
+        );
+        assert_eq!(receiver.poll().unwrap(), None);
+        fake.chip().rx_fifo.extend([10, 1, 2, 3]);
+        assert_eq!(receiver.poll().unwrap(), None);
+        assert!(receiver.partial.is_some());
+
+        fake.chip().rx_fifo.extend([0; 64]);
+        fake.chip().state = ChipState::RxfifoOverflow;
+        assert_eq!(receiver.poll().unwrap(), None);
+        assert_eq!(receiver.drops(), 1);
+        assert_eq!(receiver.partial, None);
+        let chip = fake.chip();
+        assert!(chip.rx_fifo.is_empty());
+        assert_eq!(chip.state, ChipState::Rx);
+        assert_eq!(
+            chip.strobes[3..],
+            [
+                CommandStrobe::SIDLE,
+                CommandStrobe::SFRX,
+                CommandStrobe::SRX
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_keeps_last_byte() {
+        let mut port = FakePort::default();
+        let fake = port.clone();
+        let mut cc1101 = Cc1101::new(&mut port);
+        let mut receiver = cc1101.receiver(RxMode::Raw, Duration::ZERO);
+        fake.chip().state = ChipState::Rx;
+        receiver.started = true;
+        fake.chip().rx_fifo.push_back(1);
+        assert_eq!(receiver.poll().unwrap(), None);
+        fake.chip().rx_fifo.extend([2, 3]);
+        assert_eq!(receiver.poll().unwrap(), Some(RxItem::Chunk(vec![1, 2])));
+        fake.chip().state = ChipState::Idle;
+        assert_eq!(receiver.poll().unwrap(), Some(RxItem::Chunk(vec![3])));
+    }
+}
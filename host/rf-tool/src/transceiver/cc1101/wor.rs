@@ -0,0 +1,187 @@
+//! Wake-on-Radio configuration, see datasheet section 19.5
+
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use super::{Cc1101, CommandStrobe, ConfigRegAddr, ReadWrite, Status, StatusRegAddr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorConfigError {
+    /// Event0 timeout does not fit in `EVENT0` with any `WOR_RES`
+    Event0OutOfRange,
+    /// RX timeout is shorter than the shortest `RX_TIME` for the chosen `EVENT0`
+    RxTimeoutTooShort,
+}
+impl Display for WorConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Event0OutOfRange => "event0 timeout out of range",
+            Self::RxTimeoutTooShort => "rx timeout too short",
+        })
+    }
+}
+impl std::error::Error for WorConfigError {}
+
+/// Values of `WOREVT1`/`WOREVT0`, `WORCTRL` and `MCSM2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorConfig {
+    /// `WOREVT1:WOREVT0`
+    pub event0: u16,
+    /// `WORCTRL.WOR_RES`, 0..=3
+    pub wor_res: u8,
+    /// `WORCTRL.EVENT1`, 0..=7
+    pub event1: u8,
+    /// `MCSM2.RX_TIME`, 7 means no timeout
+    pub rx_time: u8,
+    /// `MCSM2.RX_TIME_RSSI`
+    pub rx_time_rssi: bool,
+    /// `MCSM2.RX_TIME_QUAL`
+    pub rx_time_qual: bool,
+}
+impl WorConfig {
+    /// `RX_TIME` value for RX until end of packet
+    pub const RX_TIME_NO_TIMEOUT: u8 = 7;
+
+    /// Compute register values for crystal frequency `xosc_freq` (in Hz).
+    ///
+    /// The finest `WOR_RES` which can express `event0` is used, and the longest RX timeout not
+    /// exceeding `rx_timeout` is selected. `rx_timeout` of `None` stays in RX until end of packet.
+    pub fn new(
+        xosc_freq: u32,
+        event0: Duration,
+        rx_timeout: Option<Duration>,
+    ) -> Result<Self, WorConfigError> {
+        let (wor_res, event0) = (0..4)
+            .find_map(|wor_res| {
+                let v = (event0.as_secs_f64() / Self::event0_unit(xosc_freq, wor_res)).round();
+                (1.0..=u16::MAX as f64)
+                    .contains(&v)
+                    .then_some((wor_res, v as u16))
+            })
+            .ok_or(WorConfigError::Event0OutOfRange)?;
+        let mut ret = Self {
+            event0,
+            wor_res,
+            event1: 7,
+            rx_time: Self::RX_TIME_NO_TIMEOUT,
+            rx_time_rssi: false,
+            rx_time_qual: false,
+        };
+        if let Some(rx_timeout) = rx_timeout {
+            ret.rx_time = (0..Self::RX_TIME_NO_TIMEOUT)
+                .find(|&rx_time| {
+                    Self { rx_time, ..ret }
+                        .rx_timeout(xosc_freq)
+                        .is_some_and(|t| t <= rx_timeout)
+                })
+                .ok_or(WorConfigError::RxTimeoutTooShort)?;
+        }
+        Ok(ret)
+    }
+
+    /// Duration of one `EVENT0` step in seconds
+    fn event0_unit(xosc_freq: u32, wor_res: u8) -> f64 {
+        750.0 * (1u32 << (5 * wor_res)) as f64 / xosc_freq as f64
+    }
+
+    pub fn event0_timeout(&self, xosc_freq: u32) -> Duration {
+        Duration::from_secs_f64(self.event0 as f64 * Self::event0_unit(xosc_freq, self.wor_res))
+    }
+    /// RX timeout relative to Event0, `None` if RX lasts until end of packet
+    pub fn rx_timeout(&self, xosc_freq: u32) -> Option<Duration> {
+        (self.rx_time < Self::RX_TIME_NO_TIMEOUT).then(|| {
+            Duration::from_secs_f64(
+                self.event0 as f64 * 93.75 * (1 + 4 * self.wor_res as u32) as f64
+                    / ((1u32 << self.rx_time) as f64 * xosc_freq as f64),
+            )
+        })
+    }
+
+    pub const fn worevt(&self) -> [u8; 2] {
+        self.event0.to_be_bytes()
+    }
+    /// `WORCTRL` with `RC_PD=0` and `RC_CAL=1`
+    pub const fn worctrl(&self) -> u8 {
+        (self.event1 & 0x7) << 4 | 0x08 | (self.wor_res & 0x3)
+    }
+    pub const fn mcsm2(&self) -> u8 {
+        (self.rx_time_rssi as u8) << 4 | (self.rx_time_qual as u8) << 3 | (self.rx_time & 0x7)
+    }
+}
+
+impl<'a, P: Read + Write> Cc1101<'a, P> {
+    /// Write `WOREVT1`, `WOREVT0`, `WORCTRL` and `MCSM2`
+    pub fn configure_wor(&mut self, config: &WorConfig) -> io::Result<Status> {
+        let [evt1, evt0] = config.worevt();
+        self.write_raw_config_burst(ConfigRegAddr::WOREVT1, &[evt1, evt0, config.worctrl()])?;
+        Ok(self
+            .write_raw_config_reg(true, ConfigRegAddr::MCSM2, config.mcsm2())?
+            .0)
+    }
+    /// Configure Wake-on-Radio, then go to IDLE and strobe `SWOR`
+    pub fn enter_wor(&mut self, config: &WorConfig) -> io::Result<Status> {
+        self.configure_wor(config)?;
+        self.command_strobe(false, ReadWrite::Write, CommandStrobe::SIDLE)?;
+        self.command_strobe(true, ReadWrite::Write, CommandStrobe::SWOR)
+    }
+    /// Read `WORTIME1:WORTIME0`, retrying if the high byte changed in between
+    pub fn read_wor_time(&mut self) -> io::Result<u16> {
+        let mut high = self.read_raw_status_reg(false, StatusRegAddr::WORTIME1)?.1;
+        loop {
+            let low = self.read_raw_status_reg(false, StatusRegAddr::WORTIME0)?.1;
+            let high_again = self.read_raw_status_reg(true, StatusRegAddr::WORTIME1)?.1;
+            if high == high_again {
+                return Ok(u16::from_be_bytes([high, low]));
+            }
+            high = high_again;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XOSC: u32 = 26_000_000;
+
+    #[test]
+    fn compute_registers() {
+        // 750 / 26 MHz = 28.85 us per EVENT0 step with WOR_RES=0
+        let config = WorConfig::new(XOSC, Duration::from_secs(1), None).unwrap();
+        assert_eq!((config.wor_res, config.event0), (0, 34667));
+        assert_eq!(config.mcsm2(), 0x07);
+        assert_eq!(config.worctrl(), 0x78);
+
+        // needs WOR_RES=1
+        let config = WorConfig::new(XOSC, Duration::from_secs(10), None).unwrap();
+        assert_eq!((config.wor_res, config.event0), (1, 10833));
+
+        // RX_TIME=0 with WOR_RES=0 is 12.5% of event0, halved for each step
+        let config = WorConfig::new(
+            XOSC,
+            Duration::from_secs(1),
+            Some(Duration::from_millis(20)),
+        )
+        .unwrap();
+        assert_eq!(config.rx_time, 3);
+        assert_eq!(config.mcsm2(), 0x03);
+        let config = WorConfig::new(
+            XOSC,
+            Duration::from_secs(1),
+            Some(Duration::from_millis(10)),
+        )
+        .unwrap();
+        assert_eq!(config.rx_time, 4);
+        assert_eq!(
+            WorConfig::new(
+                XOSC,
+                Duration::from_secs(1),
+                Some(Duration::from_micros(100))
+            ),
+            Err(WorConfigError::RxTimeoutTooShort)
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::{fs::File, process::ExitCode, time::SystemTime};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use rf_tool::trace::{decode, read_entries, Direction};
+
+/// Print serial traffic recorded with `--trace`
+#[derive(Debug, clap::Parser)]
+struct Cli {
+    input: String,
+    /// Print raw bytes with timestamps instead of decoded transactions
+    #[arg(long)]
+    raw: bool,
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let entries = read_entries(File::open(cli.input).context("failed to open trace file")?)
+        .context("failed to read trace file")?;
+
+    if cli.raw {
+        let start = entries
+            .first()
+            .map_or(SystemTime::UNIX_EPOCH, |e| e.timestamp);
+        for entry in entries {
+            println!(
+                "[{:.6}] {} {:02x?}",
+                entry
+                    .timestamp
+                    .duration_since(start)
+                    .unwrap_or_default()
+                    .as_secs_f64(),
+                match entry.direction {
+                    Direction::Tx => ">",
+                    Direction::Rx => "<",
+                },
+                entry.data
+            );
+        }
+    } else {
+        for transaction in decode(&entries) {
+            println!("{transaction}");
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
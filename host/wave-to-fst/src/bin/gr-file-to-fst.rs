@@ -6,6 +6,7 @@ use std::{
 
 use anyhow::Context;
 use clap::Parser;
+use gr_file_to_fst::FstError;
 
 const VAR_NAME: &str = "sig";
 
@@ -17,20 +18,6 @@ struct Cli {
     output_file: String,
 }
 
-/// [fstapi::Error] does not implement [std::error::Error], so wrap it
-struct FstError(fstapi::Error);
-impl std::fmt::Debug for FstError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
-    }
-}
-impl std::fmt::Display for FstError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
-    }
-}
-impl std::error::Error for FstError {}
-
 fn run() -> anyhow::Result<()> {
     let cli = Cli::parse();
     let input = io::BufReader::new(
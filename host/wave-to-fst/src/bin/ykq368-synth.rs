@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    io::{self, Write},
+    process::ExitCode,
+    time::Duration,
+};
+
+use anyhow::Context;
+use clap::Parser;
+use gr_file_to_fst::FstError;
+use rf_tool::remote_control::{
+    atsmart::ykq368::{
+        parse_u16, parse_u64,
+        waveform::{write_packed, Edge},
+        InstrTiming, SendInstr, SendParts,
+    },
+    ev1527::{Ev1527, Ev1527Code},
+    princeton::{Pt2262, Pt2262Code, Trit},
+    pwm::{self, Pulse, RemoteProtocol},
+};
+
+const VAR_NAME: &str = "sig";
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Format {
+    /// `<time ns> <level>` per line
+    Edges,
+    /// Packed samples, as read by `ykq368-decoder` and `gr-file-to-fst`
+    Packed,
+    Fst,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Protocol {
+    /// YKQ368 instruction as the FPGA sends it
+    Ykq368,
+    /// `--data` holds the 20 bit ID and 4 data bits
+    Ev1527,
+    /// Needs `--code`
+    Pt2262,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Send {
+    Preamble,
+    Data,
+    All,
+}
+
+/// 12 of `0`, `1` and `F`, address pins first
+fn parse_pt2262(s: &str) -> Result<Pt2262Code, String> {
+    let trits = s
+        .chars()
+        .map(|c| match c {
+            '0' => Ok(Trit::Zero),
+            '1' => Ok(Trit::One),
+            'F' | 'f' => Ok(Trit::Float),
+            _ => Err(format!("invalid trit {c:?}, expected 0, 1 or F")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    trits
+        .try_into()
+        .map(Pt2262Code)
+        .map_err(|trits: Vec<_>| format!("expected 12 trits, found {}", trits.len()))
+}
+
+#[derive(Debug, Parser)]
+struct Cli {
+    #[arg(long, value_enum, default_value = "packed")]
+    format: Format,
+    /// Sample period of packed output
+    #[arg(long, default_value_t = 10)]
+    sample_period_us: u64,
+    /// Idle time before the instruction and after its last frame
+    #[arg(long, default_value_t = 10)]
+    idle_ms: u64,
+    #[arg(long, value_enum, default_value = "ykq368")]
+    protocol: Protocol,
+    #[arg(long, value_enum, default_value = "all")]
+    send: Send,
+    #[arg(long, value_parser = parse_u16, default_value = "0")]
+    preamble: u16,
+    #[arg(long, value_parser = parse_u64, default_value = "0")]
+    data: u64,
+    /// PT2262 code, 12 of `0`, `1` and `F`
+    #[arg(long, value_parser = parse_pt2262)]
+    code: Option<Pt2262Code>,
+    #[arg(long, default_value_t = 0)]
+    skip: u8,
+    #[arg(long, default_value_t = 1)]
+    repeat: u8,
+    output_file: String,
+}
+
+fn write_fst(path: String, edges: impl Iterator<Item = Edge>, end: Duration) -> anyhow::Result<()> {
+    let mut output = fstapi::Writer::create(path, true)
+        .map_err(FstError)
+        .context("failed to open output file")?
+        .timescale(-9);
+    let var = output
+        .create_var(
+            fstapi::var_type::VCD_REG,
+            fstapi::var_dir::INPUT,
+            1,
+            VAR_NAME,
+            None,
+        )
+        .map_err(FstError)
+        .context("failed to create fst var")?;
+    output
+        .emit_value_change(var, b"0")
+        .map_err(FstError)
+        .context("failed to write init value")?;
+    for edge in edges {
+        output
+            .emit_time_change(edge.time.as_nanos() as u64)
+            .map_err(FstError)
+            .context("failed to write fst time")?;
+        output
+            .emit_value_change(var, if edge.high { b"1" } else { b"0" })
+            .map_err(FstError)
+            .context("failed to write value change")?;
+    }
+    output
+        .emit_time_change(end.as_nanos() as u64)
+        .map_err(FstError)
+        .context("failed to write end time")?;
+    output.flush();
+    Ok(())
+}
+
+/// `code` sent `repeat` times back to back, and the time until the last frame ends
+fn encode<P: RemoteProtocol>(protocol: &P, code: P::Code, repeat: u8) -> (Vec<Edge>, Duration)
+where
+    P::Code: Copy,
+{
+    let pulses: Vec<Pulse> = (0..repeat).flat_map(|_| protocol.encode(code)).collect();
+    let airtime = pulses.iter().map(|p| p.high + p.low).sum();
+    (pwm::edges(Duration::ZERO, pulses).collect(), airtime)
+}
+
+fn run(cli: Cli) -> anyhow::Result<()> {
+    let (edges, airtime) = match cli.protocol {
+        Protocol::Ykq368 => {
+            let instr = SendInstr::builder(match cli.send {
+                Send::Preamble => SendParts::Preamble,
+                Send::Data => SendParts::Data,
+                Send::All => SendParts::All,
+            })
+            .preamble(cli.preamble)
+            .data(cli.data)
+            .skip(cli.skip)
+            .repeat(cli.repeat)
+            .build()
+            .context("invalid instruction")?;
+            let timing = InstrTiming::default();
+            (instr.edges(&timing).collect(), instr.airtime(&timing))
+        }
+        Protocol::Ev1527 => {
+            anyhow::ensure!(cli.data <= 0xff_ffff, "EV1527 data has 24 bits");
+            let code = Ev1527Code {
+                id: (cli.data >> 4) as u32,
+                data: (cli.data & 0xf) as u8,
+            };
+            encode(&Ev1527::default(), code, cli.repeat)
+        }
+        Protocol::Pt2262 => {
+            let code = cli.code.context("--code is required for pt2262")?;
+            encode(&Pt2262::default(), code, cli.repeat)
+        }
+    };
+
+    let idle = Duration::from_millis(cli.idle_ms);
+    let end = idle + airtime + idle;
+    let edges = edges.into_iter().map(|e| Edge {
+        time: e.time + idle,
+        ..e
+    });
+    match cli.format {
+        Format::Edges => {
+            let mut output = io::BufWriter::new(
+                fs::File::create(&cli.output_file).context("failed to create output file")?,
+            );
+            for edge in edges {
+                writeln!(output, "{} {}", edge.time.as_nanos(), edge.high as u8)
+                    .context("failed to write edge")?;
+            }
+            output.flush().context("failed to write output")?;
+        }
+        Format::Packed => {
+            let mut output =
+                fs::File::create(&cli.output_file).context("failed to create output file")?;
+            write_packed(
+                edges,
+                Duration::from_micros(cli.sample_period_us),
+                end,
+                &mut output,
+            )
+            .context("failed to write output")?;
+        }
+        Format::Fst => write_fst(cli.output_file, edges, end)?,
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
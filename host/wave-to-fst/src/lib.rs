@@ -0,0 +1,13 @@
+/// [fstapi::Error] does not implement [std::error::Error], so wrap it
+pub struct FstError(pub fstapi::Error);
+impl std::fmt::Debug for FstError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl std::fmt::Display for FstError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl std::error::Error for FstError {}
@@ -1,10 +1,22 @@
 use std::{
     env::args,
+    fmt::Debug,
     fs::File,
     io::{self, Read},
     iter::once,
+    time::Duration,
 };
 
+use rf_tool::remote_control::{
+    atsmart::ykq368::waveform::Ykq368Protocol,
+    ev1527::Ev1527,
+    princeton::Pt2262,
+    pwm::{self, read_packed, PulseError, RemoteProtocol},
+};
+
+/// Sample period the bit lengths in [decode] are counted in
+const SAMPLE_PERIOD: Duration = Duration::from_micros(10);
+
 struct DataStream {
     data_str: String,
     count: u8,
@@ -31,15 +43,7 @@ impl DataStream {
     }
 }
 
-fn main() {
-    let input_path = {
-        let mut a = args();
-        a.next().expect("missing program path");
-        a.next().expect("missing input file")
-    };
-
-    let input = io::BufReader::new(File::open(input_path).unwrap());
-
+fn decode(input: impl Iterator<Item = io::Result<u8>>) -> io::Result<DataStream> {
     let mut last_val = false;
     let mut last_pos_edge = 0;
     let mut last_neg_edge = 0;
@@ -49,8 +53,8 @@ fn main() {
         count: 0,
     };
 
-    for (byte_idx, byte) in input.bytes().chain(once(Ok(1))).enumerate() {
-        let mut byte = byte.unwrap();
+    for (byte_idx, byte) in input.chain(once(Ok(1))).enumerate() {
+        let mut byte = byte?;
         for bit_idx in 0..8 {
             let v = (byte & 0x80) != 0;
             if v != last_val {
@@ -91,5 +95,133 @@ fn main() {
         }
     }
 
+    Ok(data)
+}
+
+/// Frames of `protocol` in `len` bytes of packed samples, split by [RemoteProtocol::frames]
+fn decode_frames<P: RemoteProtocol>(
+    protocol: &P,
+    samples: impl Iterator<Item = io::Result<u8>>,
+    len: u64,
+) -> io::Result<Vec<Result<P::Code, PulseError>>> {
+    let end = SAMPLE_PERIOD * (len * 8) as u32;
+    let mut error = Ok(());
+    let samples = samples.map_while(|b| b.map_err(|e| error = Err(e)).ok());
+    let pulses: Vec<_> = pwm::pulses(read_packed(samples, SAMPLE_PERIOD), end).collect();
+    error?;
+    Ok(protocol
+        .frames(&pulses)
+        .map(|frame| protocol.decode(frame))
+        .collect())
+}
+
+fn print_frames<P: RemoteProtocol>(protocol: &P, input_path: &str)
+where
+    P::Code: Debug,
+{
+    let input = File::open(input_path).unwrap();
+    let len = input.metadata().unwrap().len();
+    println!("\nframes:");
+    for frame in decode_frames(protocol, io::BufReader::new(input).bytes(), len).unwrap() {
+        match frame {
+            Ok(code) => println!("{code:?}"),
+            Err(e) => println!("? {e:?}"),
+        }
+    }
+}
+
+fn main() {
+    let (input_path, protocol) = {
+        let mut a = args();
+        a.next().expect("missing program path");
+        (
+            a.next().expect("missing input file"),
+            a.next().unwrap_or_else(|| "ykq368".to_owned()),
+        )
+    };
+
+    let input = io::BufReader::new(File::open(&input_path).unwrap());
+    let data = decode(input.bytes()).unwrap();
+
     println!("\ndata:\n{}", data.data_str);
+
+    match protocol.as_str() {
+        "ykq368" => print_frames(&Ykq368Protocol::default(), &input_path),
+        "ev1527" => print_frames(&Ev1527::default(), &input_path),
+        "pt2262" => print_frames(&Pt2262::default(), &input_path),
+        _ => panic!("unknown protocol {protocol}, expected ykq368, ev1527 or pt2262"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rf_tool::remote_control::atsmart::ykq368::{
+        waveform::{write_packed, DataFrame, Edge},
+        Data, InstrTiming, SendInstr, SendParts,
+    };
+
+    use super::*;
+
+    #[test]
+    fn synthesized_loopback() {
+        let timing = InstrTiming::default();
+        let lead = Duration::from_millis(2);
+        for data in [0x1_2345_6326, 0x1_2345_6315, 0x7_ffff_ffff, 0x0_0000_0001] {
+            let instr = SendInstr::builder(SendParts::All)
+                .preamble(0x1555)
+                .data(data)
+                .skip(1)
+                .repeat(3)
+                .build()
+                .unwrap();
+            let mut packed = Vec::new();
+            write_packed(
+                instr.edges(&timing).map(|e| Edge {
+                    time: e.time + lead,
+                    ..e
+                }),
+                SAMPLE_PERIOD,
+                lead + instr.airtime(&timing) + timing.data_wait,
+                &mut packed,
+            )
+            .unwrap();
+
+            // the preamble and the frame shortened by skip are not data frames
+            let frames: Vec<_> = instr
+                .frames(&timing)
+                .map(|f| match f.bits {
+                    33 | 35 => Ok(DataFrame {
+                        data: Data(f.value << (35 - f.bits)),
+                        bits: f.bits,
+                    }),
+                    found => Err(PulseError::Count {
+                        expected: 35,
+                        found: found as usize,
+                    }),
+                })
+                .collect();
+            let samples = packed.iter().copied().map(Ok);
+            assert_eq!(
+                decode_frames(&Ykq368Protocol::default(), samples, packed.len() as u64).unwrap(),
+                frames
+            );
+
+            let data = decode(packed.into_iter().map(Ok)).unwrap();
+            // the first rising edge always decodes as an unknown bit
+            let expected: Vec<_> = ["?".to_owned()]
+                .into_iter()
+                .chain(instr.frames(&timing).map(|f| {
+                    (0..f.bits)
+                        .map(|idx| if f.bit(idx) { '1' } else { '0' })
+                        .collect()
+                }))
+                .collect();
+            assert_eq!(
+                data.data_str.replace(' ', "").lines().collect::<Vec<_>>(),
+                expected
+            );
+        }
+    }
 }
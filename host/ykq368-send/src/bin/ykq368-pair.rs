@@ -0,0 +1,123 @@
+use std::{io, io::Write, path::PathBuf, process::ExitCode};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use rf_tool::remote_control::atsmart::ykq368::{
+    pairing::{self, Source},
+    parse_u16,
+    remotes::{self, RemotesFile},
+    InstrTiming, Preamble, Ykq368Endpoint,
+};
+
+/// Add a virtual remote to the remotes file, then pair it with a motor if a port is given
+#[derive(Debug, Parser)]
+struct Cli {
+    /// TOML, or YAML if named `*.yaml` or `*.yml`, created if missing
+    #[arg(long, default_value = "remotes.toml")]
+    remotes: PathBuf,
+    /// Remote to add, an existing one is paired without generating codes
+    #[arg(long)]
+    remote: String,
+    /// Random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+    /// 0 to 15
+    #[arg(long, default_value_t = 1)]
+    channel: u8,
+    #[arg(long, value_parser = parse_u16)]
+    preamble: Option<u16>,
+    #[arg(long, default_value_t = 3)]
+    repeat: u8,
+    /// Remote the motor already reacts to, required with `--port`
+    #[arg(long, requires = "port")]
+    paired: Option<String>,
+    #[arg(long, requires = "paired")]
+    port: Option<String>,
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut remotes = match RemotesFile::load(&cli.remotes) {
+        Ok(remotes) => remotes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => RemotesFile::default(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to load {}", cli.remotes.display()))
+        }
+    };
+    if remotes.remotes.contains_key(&cli.remote) {
+        println!("{} already exists, keeping its codes", cli.remote);
+    } else {
+        let preamble = cli
+            .preamble
+            .map(Preamble::new)
+            .transpose()
+            .context("invalid preamble")?;
+        let seed = cli.seed.unwrap_or_else(rand::random);
+        let remote =
+            pairing::generate(seed, cli.channel, preamble, cli.repeat).context("invalid remote")?;
+        println!("generating {} from seed {seed}", cli.remote);
+        remotes.remotes.insert(cli.remote.clone(), remote);
+        if remotes::is_yaml(&cli.remotes) {
+            eprintln!(
+                "warning: rewriting {}, its comments are not kept",
+                cli.remotes.display()
+            );
+        }
+        remotes
+            .save_remote(&cli.remotes, &cli.remote)
+            .with_context(|| format!("failed to save {}", cli.remotes.display()))?;
+    }
+
+    let (Some(paired), Some(port)) = (cli.paired, cli.port) else {
+        return Ok(());
+    };
+    let paired = remotes
+        .remotes
+        .get(&paired)
+        .with_context(|| format!("no remote named {paired}"))?;
+    let mut port = serialport::new(port, 115200)
+        .open_native()
+        .context("failed to open serial port")?;
+    port.write_all(&[0x03])
+        .context("failed to select endpoint")?;
+    let mut endpoint = Ykq368Endpoint::new(&mut port);
+    let done = pairing::pair(
+        &mut endpoint,
+        paired,
+        &remotes.remotes[&cli.remote],
+        &InstrTiming::default(),
+        |step, instr| {
+            println!(
+                "{:?} on {} remote ({:.1} s)",
+                step.button,
+                match step.source {
+                    Source::Paired => "paired",
+                    Source::New => "new",
+                },
+                instr.airtime(&InstrTiming::default()).as_secs_f32()
+            );
+            Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("{}?", step.expect))
+                .interact()
+                .map_err(io::Error::other)
+        },
+    )
+    .context("failed to send pairing sequence")?;
+    endpoint.exit().context("failed to exit endpoint")?;
+    if done {
+        println!("{} paired", cli.remote);
+    } else {
+        println!("pairing stopped");
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
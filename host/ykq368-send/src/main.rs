@@ -0,0 +1,88 @@
+use std::{io::Write, path::PathBuf, process::ExitCode, str::FromStr, time::Duration};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use rf_tool::remote_control::atsmart::ykq368::{
+    remotes::RemotesFile,
+    schedule::{Scheduler, Step, Timeline},
+    Button, InstrTiming, Ykq368Endpoint,
+};
+
+fn parse_button(s: &str) -> Result<Button, String> {
+    Button::from_str(s).map_err(|_| format!("unknown button {s}"))
+}
+
+/// Press a button on a remote, or on every remote of a group in order
+#[derive(Debug, Parser)]
+struct Cli {
+    #[arg(long)]
+    port: String,
+    /// TOML, or YAML if named `*.yaml` or `*.yml`
+    #[arg(long, default_value = "remotes.toml")]
+    remotes: PathBuf,
+    /// Remote or group name
+    #[arg(long)]
+    remote: String,
+    #[arg(value_parser = parse_button)]
+    button: Button,
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let remotes = RemotesFile::load(&cli.remotes)
+        .with_context(|| format!("failed to load {}", cli.remotes.display()))?;
+    let instrs = remotes.instrs(&cli.remote, cli.button)?;
+    for (name, instr) in &instrs {
+        if remotes.remotes[*name].derived(cli.button).is_some() {
+            eprintln!(
+                "warning: {name} has no code for {:?}, using derived {:?}",
+                cli.button, instr.data
+            );
+        }
+    }
+
+    let mut port = serialport::new(cli.port, 115200)
+        .open_native()
+        .context("failed to open serial port")?;
+    port.write_all(&[0x03])
+        .context("failed to select endpoint")?;
+
+    let mut scheduler = Scheduler::new(Ykq368Endpoint::new(&mut port), InstrTiming::default());
+    let report = scheduler
+        .run(&Timeline {
+            steps: instrs
+                .iter()
+                .map(|(_, instr)| Step {
+                    delay: Duration::ZERO,
+                    instr: *instr,
+                })
+                .collect(),
+            on_cancel: None,
+        })
+        .context("failed to send command")?;
+    for sent in report.sent {
+        if let Some(step) = sent.step {
+            println!(
+                "[{:.3}] {} {:?} ({:.3} s)",
+                sent.written.as_secs_f32(),
+                instrs[step].0,
+                cli.button,
+                sent.airtime.as_secs_f32()
+            );
+        }
+    }
+    scheduler
+        .into_endpoint()
+        .exit()
+        .context("failed to exit endpoint")?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
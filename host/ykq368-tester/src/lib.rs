@@ -5,7 +5,7 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use rf_tool::remote_control::atsmart::ykq368::{SendInstr, Ykq368Endpoint};
+use rf_tool::remote_control::atsmart::ykq368::{Button, InstrTiming, SendInstr, Ykq368Endpoint};
 use rustix::{
     event::{poll, PollFd, PollFlags},
     termios::{tcgetattr, tcsetattr, LocalModes, OptionalActions},
@@ -60,6 +60,34 @@ pub enum Key {
     Lock,
     Stop,
 }
+impl Key {
+    pub const fn button(self) -> Button {
+        match self {
+            Key::Close => Button::Close,
+            Key::Open => Button::Open,
+            Key::Lock => Button::Lock,
+            Key::Stop => Button::Stop,
+        }
+    }
+}
+impl From<Key> for Button {
+    fn from(value: Key) -> Self {
+        value.button()
+    }
+}
+impl TryFrom<Button> for Key {
+    type Error = Button;
+    fn try_from(value: Button) -> Result<Self, Self::Error> {
+        match value {
+            Button::Close => Ok(Key::Close),
+            Button::Open => Ok(Key::Open),
+            Button::Lock => Ok(Key::Lock),
+            Button::Stop => Ok(Key::Stop),
+            Button::Program => Err(value),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct KeyConfig<T> {
     pub close: T,
@@ -89,20 +117,20 @@ pub enum TestResult {
     Fail,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestKey {
     pub key: Key,
     pub result: TestResult,
     pub timestamp: SystemTime,
     pub instr: SendInstr,
 }
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TestCycle {
     pub start_time: SystemTime,
     pub end_time: SystemTime,
     pub test_keys: Vec<TestKey>,
 }
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TestRun {
     pub id: Uuid,
     pub instr: KeyConfig<SendInstr>,
@@ -172,6 +200,7 @@ pub struct CycleTester<'t, 'a, W> {
     failed: Vec<TestKey>,
 }
 impl<'t, 'a, W: std::io::Write> CycleTester<'t, 'a, W> {
+    /// `wait` starts after the instruction has been sent
     pub fn send(&mut self, key: Key, wait: Duration) -> Result<TestResult> {
         let timestamp = SystemTime::now();
         println!(
@@ -186,7 +215,8 @@ impl<'t, 'a, W: std::io::Write> CycleTester<'t, 'a, W> {
             .sender
             .send(self.tester.instr[key].to_command())
             .context("failed to send command")?;
-        let result = match wait_or_interrupt(wait).context("failed to wait on input")? {
+        let airtime = self.tester.instr[key].airtime(&InstrTiming::default());
+        let result = match wait_or_interrupt(airtime + wait).context("failed to wait on input")? {
             WaitResult::Normal => TestResult::Pass,
             WaitResult::Interrupted => {
                 TestResult::VARIANTS[dialoguer::Select::with_theme(
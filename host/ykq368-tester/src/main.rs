@@ -1,6 +1,6 @@
-use std::{io::Write, process::ExitCode, time::Duration};
+use std::{io::Write, path::PathBuf, process::ExitCode, time::Duration};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use dialoguer::theme::ColorfulTheme;
 use rustix::{
@@ -9,39 +9,43 @@ use rustix::{
 };
 use strum::VariantArray;
 
-use rf_tool::remote_control::atsmart::ykq368::{Data, Preamble, SendInstr, SendParts};
+use rf_tool::remote_control::atsmart::ykq368::{
+    remotes::{Remote, RemotesFile},
+    SendInstr, SendParts,
+};
 use ykq368_tester::{
     wait_or_interrupt, CycleTester, Key, KeyConfig, TestResult, Tester, WaitResult,
 };
 
-// fill in data from decoder
-const PREAMBLE: Preamble = panic!("missing preamble");
-
-const CLOSE: Data = panic!("missing close data");
-const OPEN: Data = panic!("missing open data");
-const LOCK: Data = panic!("missing lock data");
-const STOP: Data = panic!("missing stop data");
-
-const fn default_instr(has_preamble: bool, data: Data) -> SendInstr {
-    SendInstr {
-        send: if has_preamble {
-            SendParts::All
-        } else {
-            SendParts::Data
-        },
-        skip: 0,
-        preamble: PREAMBLE,
-        data,
-        repeat: 3,
-    }
-}
-const fn default_keys(has_preamble: bool) -> KeyConfig<SendInstr> {
-    KeyConfig {
-        close: default_instr(has_preamble, CLOSE),
-        open: default_instr(has_preamble, OPEN),
-        lock: default_instr(has_preamble, LOCK),
-        stop: default_instr(has_preamble, STOP),
+/// Instructions of `remote`, with its preamble if `has_preamble`
+fn remote_keys(remote: &Remote, has_preamble: bool) -> Result<KeyConfig<SendInstr>> {
+    if has_preamble && remote.preamble.is_none() {
+        bail!("remote has no preamble");
     }
+    let instr = |key: Key| -> Result<SendInstr> {
+        let mut instr = remote
+            .instr(key.button())
+            .with_context(|| format!("remote has no code for {key:?}"))?;
+        if remote.derived(key.button()).is_some() {
+            eprintln!(
+                "warning: remote has no code for {key:?}, using derived {:?}",
+                instr.data
+            );
+        }
+        if !has_preamble {
+            instr.send = SendParts::Data;
+        }
+        instr
+            .validate()
+            .with_context(|| format!("invalid instruction for {key:?}"))?;
+        Ok(instr)
+    };
+    Ok(KeyConfig {
+        close: instr(Key::Close)?,
+        open: instr(Key::Open)?,
+        lock: instr(Key::Lock)?,
+        stop: instr(Key::Stop)?,
+    })
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
@@ -62,6 +66,12 @@ struct Cli {
     /// directory to write results to
     #[arg(long)]
     dest: String,
+    /// TOML, or YAML if named `*.yaml` or `*.yml`
+    #[arg(long, default_value = "remotes.toml")]
+    remotes: PathBuf,
+    /// remote to test, buttons without code are derived from another one
+    #[arg(long)]
+    remote: String,
 }
 
 fn before_start(dur: Duration) -> Result<bool> {
@@ -204,6 +214,18 @@ fn run_test<W: std::io::Write>(
     Ok(())
 }
 fn run(cli: Cli) -> Result<()> {
+    let remotes = RemotesFile::load(&cli.remotes)
+        .with_context(|| format!("failed to load {}", cli.remotes.display()))?;
+    let remote = remotes
+        .remotes
+        .get(&cli.remote)
+        .with_context(|| format!("no remote named {}", cli.remote))?;
+    let data_only = remote_keys(remote, false)?;
+    let with_preamble = match cli.encoding {
+        Encoding::DataOnly => data_only,
+        Encoding::PreambleAndData | Encoding::Random => remote_keys(remote, true)?,
+    };
+
     let mut port = serialport::new(cli.port, 115200)
         .open_native()
         .context("failed to open serial port")?;
@@ -236,7 +258,11 @@ fn run(cli: Cli) -> Result<()> {
         if !before_start(Duration::from_secs(240))? {
             break;
         }
-        run_test(root.as_fd(), default_keys(cfg), &mut port)?;
+        run_test(
+            root.as_fd(),
+            if cfg { with_preamble } else { data_only },
+            &mut port,
+        )?;
     }
 
     Ok(())